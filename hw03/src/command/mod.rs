@@ -0,0 +1,178 @@
+use std::fmt;
+
+use crate::paint::PaintMode;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    // 1 x y - переместить курсор в позицию x y
+    MoveCursor(u64, u64),
+    // 2 colour - перекрасить пиксель под курсором в цвет colour
+    Paint(u8),
+    // 3 mode [opacity] - режим рисования: 1 - замена, 2 opacity - наложение
+    // с непрозрачностью 0..=100, 3 - XOR, 4 - только поверх цвета фона
+    SetPaintMode(PaintMode),
+    // 4 - очистить дисплей цветом фона
+    Clear,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    UnknownCommand(u64),
+    MissingArgument,
+    IncorrectColour(u64),
+    IncorrectPaintMode(u64),
+    IncorrectOpacity(u64),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnknownCommand(code) => write!(f, "Unknown command: {}", code),
+            ParseError::MissingArgument => write!(f, "Not enought command"),
+            ParseError::IncorrectColour(colour) => write!(f, "Incorrect colour: {}", colour),
+            ParseError::IncorrectPaintMode(mode) => write!(f, "Incorrect paint mode: {}", mode),
+            ParseError::IncorrectOpacity(opacity) => {
+                write!(f, "Incorrect opacity: {}", opacity)
+            }
+        }
+    }
+}
+
+pub fn parse_commands(input: &[u64]) -> Vec<Command> {
+    match try_parse_commands(input) {
+        Ok(commands) => commands,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+pub fn try_parse_commands(input: &[u64]) -> Result<Vec<Command>, ParseError> {
+    let mut tokens = input.iter().copied();
+    let mut commands = Vec::new();
+
+    while let Some(code) = tokens.next() {
+        let command = match code {
+            1 => {
+                let x = next_arg(&mut tokens)?;
+                let y = next_arg(&mut tokens)?;
+                Command::MoveCursor(x, y)
+            }
+            2 => {
+                let colour = next_arg(&mut tokens)?;
+                let colour =
+                    u8::try_from(colour).map_err(|_| ParseError::IncorrectColour(colour))?;
+                Command::Paint(colour)
+            }
+            3 => {
+                let mode = match next_arg(&mut tokens)? {
+                    1 => PaintMode::Replace,
+                    2 => {
+                        let opacity = next_arg(&mut tokens)?;
+                        match u8::try_from(opacity) {
+                            Ok(opacity @ 0..=100) => PaintMode::Alpha(opacity),
+                            _ => return Err(ParseError::IncorrectOpacity(opacity)),
+                        }
+                    }
+                    3 => PaintMode::Xor,
+                    4 => PaintMode::OnlyIfBackground,
+                    mode => return Err(ParseError::IncorrectPaintMode(mode)),
+                };
+                Command::SetPaintMode(mode)
+            }
+            4 => Command::Clear,
+            _ => return Err(ParseError::UnknownCommand(code)),
+        };
+        commands.push(command);
+    }
+
+    Ok(commands)
+}
+
+fn next_arg(tokens: &mut impl Iterator<Item = u64>) -> Result<u64, ParseError> {
+    tokens.next().ok_or(ParseError::MissingArgument)
+}
+
+// Команда печатается в том же числовом виде, в котором её вводит пользователь
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::MoveCursor(x, y) => write!(f, "1 {} {}", x, y),
+            Command::Paint(colour) => write!(f, "2 {}", colour),
+            Command::SetPaintMode(PaintMode::Replace) => write!(f, "3 1"),
+            Command::SetPaintMode(PaintMode::Alpha(opacity)) => write!(f, "3 2 {}", opacity),
+            Command::SetPaintMode(PaintMode::Xor) => write!(f, "3 3"),
+            Command::SetPaintMode(PaintMode::OnlyIfBackground) => write!(f, "3 4"),
+            Command::Clear => write!(f, "4"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sequence() {
+        let commands = parse_commands(&[1, 2, 2, 2, 3, 2, 1]);
+        assert_eq!(
+            commands,
+            vec![
+                Command::MoveCursor(2, 2),
+                Command::Paint(3),
+                Command::Paint(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let commands = vec![
+            Command::MoveCursor(0, 3),
+            Command::Paint(2),
+            Command::SetPaintMode(PaintMode::Alpha(40)),
+            Command::SetPaintMode(PaintMode::Xor),
+            Command::SetPaintMode(PaintMode::OnlyIfBackground),
+            Command::SetPaintMode(PaintMode::Replace),
+            Command::Clear,
+        ];
+        let line = commands
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let tokens: Vec<u64> = line
+            .split_whitespace()
+            .map(|t| t.parse().unwrap())
+            .collect();
+        assert_eq!(parse_commands(&tokens), commands);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_missing_argument() {
+        parse_commands(&[1, 2]);
+    }
+
+    #[test]
+    fn test_unknown_command() {
+        assert_eq!(
+            try_parse_commands(&[7, 1]),
+            Err(ParseError::UnknownCommand(7))
+        );
+        assert_eq!(
+            try_parse_commands(&[2, 300]),
+            Err(ParseError::IncorrectColour(300))
+        );
+        assert_eq!(
+            try_parse_commands(&[3, 5]),
+            Err(ParseError::IncorrectPaintMode(5))
+        );
+        assert_eq!(
+            try_parse_commands(&[3, 2, 101]),
+            Err(ParseError::IncorrectOpacity(101))
+        );
+        assert_eq!(
+            try_parse_commands(&[3, 2]),
+            Err(ParseError::MissingArgument)
+        );
+    }
+}
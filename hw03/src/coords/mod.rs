@@ -0,0 +1,161 @@
+// Система координат дисплея.
+//
+// Внутри матрица всегда хранится построчно: (строка, столбец), строка 0 - верхняя.
+// Координаты из команд переводятся в ячейку матрицы в соответствии с выбранной
+// системой, поэтому проверка границ и отрисовка не зависят от соглашения.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Axes {
+    // Первая координата - строка, вторая - столбец (так работал дисплей изначально)
+    #[default]
+    RowCol,
+    // Первая координата - x (столбец), вторая - y (строка)
+    XY,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Origin {
+    #[default]
+    TopLeft,
+    BottomLeft,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CoordinateSystem {
+    pub axes: Axes,
+    pub origin: Origin,
+}
+
+impl CoordinateSystem {
+    pub fn new(axes: Axes, origin: Origin) -> Self {
+        Self { axes, origin }
+    }
+
+    // Переводит координаты команды в ячейку матрицы (строка, столбец).
+    // Возвращает None, если точка за пределами дисплея.
+    pub fn cell(&self, a: u64, b: u64, width: u32, height: u32) -> Option<(usize, usize)> {
+        let (row, col) = match self.axes {
+            Axes::RowCol => (a, b),
+            Axes::XY => (b, a),
+        };
+
+        if row >= height as u64 || col >= width as u64 {
+            return None;
+        }
+
+        let row = match self.origin {
+            Origin::TopLeft => row,
+            Origin::BottomLeft => height as u64 - 1 - row,
+        };
+
+        Some((row as usize, col as usize))
+    }
+
+    // Обратное преобразование: ячейка матрицы в координаты команды
+    pub fn position(&self, row: usize, col: usize, height: u32) -> (u64, u64) {
+        let row = match self.origin {
+            Origin::TopLeft => row as u64,
+            Origin::BottomLeft => height as u64 - 1 - row as u64,
+        };
+
+        match self.axes {
+            Axes::RowCol => (row, col as u64),
+            Axes::XY => (col as u64, row),
+        }
+    }
+}
+
+impl fmt::Display for Axes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Axes::RowCol => write!(f, "rowcol"),
+            Axes::XY => write!(f, "xy"),
+        }
+    }
+}
+
+impl FromStr for Axes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rowcol" => Ok(Axes::RowCol),
+            "xy" => Ok(Axes::XY),
+            _ => Err(format!("Неизвестные оси: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::TopLeft => write!(f, "top-left"),
+            Origin::BottomLeft => write!(f, "bottom-left"),
+        }
+    }
+}
+
+impl FromStr for Origin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top-left" => Ok(Origin::TopLeft),
+            "bottom-left" => Ok(Origin::BottomLeft),
+            _ => Err(format!("Неизвестное начало координат: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_row_col_top_left() {
+        let coords = CoordinateSystem::default();
+        assert_eq!(coords.cell(1, 3, 5, 2), Some((1, 3)));
+        assert_eq!(coords.cell(2, 0, 5, 2), None);
+        assert_eq!(coords.cell(0, 5, 5, 2), None);
+    }
+
+    #[test]
+    fn test_xy_top_left() {
+        let coords = CoordinateSystem::new(Axes::XY, Origin::TopLeft);
+        assert_eq!(coords.cell(4, 1, 5, 2), Some((1, 4)));
+        assert_eq!(coords.cell(1, 4, 5, 2), None);
+    }
+
+    #[test]
+    fn test_xy_bottom_left() {
+        let coords = CoordinateSystem::new(Axes::XY, Origin::BottomLeft);
+        assert_eq!(coords.cell(0, 0, 5, 3), Some((2, 0)));
+        assert_eq!(coords.cell(4, 2, 5, 3), Some((0, 4)));
+        assert_eq!(coords.cell(0, 3, 5, 3), None);
+    }
+
+    #[test]
+    fn test_position_is_inverse() {
+        for axes in [Axes::RowCol, Axes::XY] {
+            for origin in [Origin::TopLeft, Origin::BottomLeft] {
+                let coords = CoordinateSystem::new(axes, origin);
+                for row in 0..3 {
+                    for col in 0..5 {
+                        let (a, b) = coords.position(row, col, 3);
+                        assert_eq!(coords.cell(a, b, 5, 3), Some((row, col)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("xy".parse(), Ok(Axes::XY));
+        assert_eq!("bottom-left".parse(), Ok(Origin::BottomLeft));
+        assert!("yx".parse::<Axes>().is_err());
+    }
+}
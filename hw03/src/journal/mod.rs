@@ -0,0 +1,309 @@
+// Журнал команд дисплея.
+//
+// Формат файла: первая строка - заголовок с параметрами дисплея
+// (размеры, цвет по умолчанию и система координат),
+// далее по одной строке на каждую применённую команду:
+//
+// hw03-journal 4 4 1 rowcol top-left
+// 1 1697040000123 1 2 2
+// 2 1697040000124 2 3
+//
+// где первые два числа - номер записи и время в миллисекундах с начала эпохи,
+// остальное - команда в том же виде, в котором её вводит пользователь.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::command::{Command, try_parse_commands};
+use crate::coords::CoordinateSystem;
+use crate::matrix::MAX_SIDE;
+use crate::{Display, create_display_with_coords, palette, try_apply_command};
+
+const HEADER: &str = "hw03-journal";
+
+pub struct Journal {
+    file: File,
+    next_seq: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct JournalEntry {
+    pub seq: u64,
+    pub timestamp_ms: u128,
+    pub command: Command,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct JournalLog {
+    pub width: u32,
+    pub height: u32,
+    pub default_colour: u8,
+    pub coords: CoordinateSystem,
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        width: u32,
+        height: u32,
+        default_colour: u8,
+        coords: CoordinateSystem,
+    ) -> io::Result<Self> {
+        let mut file = File::create(path)?;
+        writeln!(
+            file,
+            "{} {} {} {} {} {}",
+            HEADER, width, height, default_colour, coords.axes, coords.origin
+        )?;
+        Ok(Self { file, next_seq: 1 })
+    }
+
+    pub fn record(&mut self, command: &Command) -> io::Result<u64> {
+        let seq = self.next_seq;
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+
+        // Строка пишется одним вызовом, чтобы при падении не осталось половины записи
+        let line = format!("{} {} {}\n", seq, timestamp_ms, command);
+        self.file.write_all(line.as_bytes())?;
+
+        self.next_seq += 1;
+        Ok(seq)
+    }
+}
+
+pub fn read_journal<P: AsRef<Path>>(path: P) -> io::Result<JournalLog> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+
+    let header = lines.next().ok_or_else(|| invalid("Пустой журнал"))??;
+    let header: Vec<&str> = header.split_whitespace().collect();
+    if header.len() != 6 || header[0] != HEADER {
+        return Err(invalid("Неверный заголовок журнала"));
+    }
+
+    let mut log = JournalLog {
+        width: parse_field(header[1])?,
+        height: parse_field(header[2])?,
+        default_colour: parse_field(header[3])?,
+        coords: CoordinateSystem {
+            axes: header[4].parse().map_err(|e: String| invalid(&e))?,
+            origin: header[5].parse().map_err(|e: String| invalid(&e))?,
+        },
+        entries: Vec::new(),
+    };
+    for side in [log.width, log.height] {
+        if side == 0 || side > MAX_SIDE {
+            return Err(invalid(&format!(
+                "Размер дисплея должен быть от 1 до {}: {}",
+                MAX_SIDE, side
+            )));
+        }
+    }
+
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        let seq = parse_field(fields.next().unwrap_or_default())?;
+        // replay останавливается на первой записи с большим номером, поэтому
+        // переставленная или повторённая запись - это порча журнала
+        if log.entries.last().is_some_and(|last| seq <= last.seq) || seq == 0 {
+            return Err(invalid(&format!(
+                "Нарушен порядок записей на записи {}",
+                seq
+            )));
+        }
+        let timestamp_ms = parse_field(fields.next().unwrap_or_default())?;
+        let tokens = fields.map(parse_field).collect::<io::Result<Vec<u64>>>()?;
+
+        let command = match try_parse_commands(&tokens).as_deref() {
+            Ok([command]) => *command,
+            _ => return Err(invalid(&format!("Неверная команда в записи {}", seq))),
+        };
+
+        log.entries.push(JournalEntry {
+            seq,
+            timestamp_ms,
+            command,
+        });
+    }
+
+    Ok(log)
+}
+
+// Восстанавливает состояние дисплея после применения всех записей
+// с номером не больше seq (или всего журнала, если seq не указан).
+// Журнал мог быть изменён вручную, поэтому запись с координатами за пределами
+// дисплея или неверным цветом - это InvalidData, а не паника.
+pub fn replay<P: AsRef<Path>>(path: P, seq: Option<u64>) -> io::Result<Display> {
+    let log = read_journal(path)?;
+    if !palette::is_ink(log.default_colour) {
+        return Err(invalid(&format!(
+            "Неверный цвет по умолчанию: {}",
+            log.default_colour
+        )));
+    }
+    let mut display =
+        create_display_with_coords(log.width, log.height, log.default_colour, log.coords);
+
+    for entry in log
+        .entries
+        .iter()
+        .take_while(|entry| seq.is_none_or(|seq| entry.seq <= seq))
+    {
+        try_apply_command(&mut display, entry.command)
+            .map_err(|err| invalid(&format!("Запись {}: {}", entry.seq, err)))?;
+    }
+
+    Ok(display)
+}
+
+fn parse_field<T: std::str::FromStr>(field: &str) -> io::Result<T> {
+    field
+        .parse()
+        .map_err(|_| invalid(&format!("Неверное значение в журнале: '{}'", field)))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::{Axes, Origin};
+    use crate::matrix::Matrix;
+    use crate::{create_display, process_commands};
+    use std::path::PathBuf;
+
+    fn journal_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hw03-{}-{}.journal", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = journal_path("replay");
+        let mut display = create_display(4, 4, 1);
+        display.journal =
+            Some(Journal::create(&path, 4, 4, 1, CoordinateSystem::default()).unwrap());
+        process_commands(&mut display, vec![1, 2, 2, 2, 3, 1, 0, 1, 2, 2]);
+
+        let replayed = replay(&path, None).unwrap();
+        assert_eq!(replayed.matrix, display.matrix);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_up_to_seq() {
+        let path = journal_path("bisect");
+        let mut display = create_display(4, 4, 1);
+        display.journal =
+            Some(Journal::create(&path, 4, 4, 1, CoordinateSystem::default()).unwrap());
+        process_commands(&mut display, vec![1, 2, 2, 2, 3, 1, 0, 1, 2, 2]);
+
+        let mut expected = Matrix::new(4, 4, 1);
+        assert_eq!(replay(&path, Some(0)).unwrap().matrix, expected);
+
+        expected.set_colour(2, 2, 3);
+        assert_eq!(replay(&path, Some(2)).unwrap().matrix, expected);
+        assert_eq!(replay(&path, Some(3)).unwrap().matrix, expected);
+
+        expected.set_colour(0, 1, 2);
+        assert_eq!(replay(&path, Some(4)).unwrap().matrix, expected);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_entries_are_numbered() {
+        let path = journal_path("numbered");
+        let mut display = create_display(3, 3, 2);
+        display.journal =
+            Some(Journal::create(&path, 3, 3, 2, CoordinateSystem::default()).unwrap());
+        process_commands(&mut display, vec![1, 1, 1, 2, 1, 2, 3]);
+
+        let log = read_journal(&path).unwrap();
+        assert_eq!((log.width, log.height, log.default_colour), (3, 3, 2));
+        let seqs: Vec<u64> = log.entries.iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, vec![1, 2, 3]);
+        assert!(
+            log.entries
+                .windows(2)
+                .all(|w| w[0].timestamp_ms <= w[1].timestamp_ms)
+        );
+        assert_eq!(log.entries[1].command, Command::Paint(1));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_keeps_coords() {
+        let path = journal_path("coords");
+        let coords = CoordinateSystem::new(Axes::XY, Origin::BottomLeft);
+        let mut display = create_display_with_coords(5, 3, 1, coords);
+        display.journal = Some(Journal::create(&path, 5, 3, 1, coords).unwrap());
+        process_commands(&mut display, vec![1, 4, 0, 2, 3]);
+
+        let replayed = replay(&path, None).unwrap();
+        assert_eq!(replayed.coords, coords);
+        assert_eq!(replayed.matrix, display.matrix);
+        assert_eq!(replayed.matrix.colour(2, 4), 3);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_rejects_invalid_entries() {
+        for (name, journal) in [
+            (
+                "outside",
+                "hw03-journal 4 4 1 rowcol top-left\n1 100 1 7 7\n",
+            ),
+            ("colour", "hw03-journal 4 4 1 rowcol top-left\n1 100 2 9\n"),
+            ("default", "hw03-journal 4 4 7 rowcol top-left\n"),
+        ] {
+            let path = journal_path(name);
+            std::fs::write(&path, journal).unwrap();
+            let err = replay(&path, None).err().unwrap();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_rejects_bad_journals() {
+        for (name, journal) in [
+            ("legacy", "hw03-journal 4 4 1\n1 100 1 1 2\n"),
+            ("huge", "hw03-journal 4000000 4000000 1 rowcol top-left\n"),
+            ("empty", "hw03-journal 0 4 1 rowcol top-left\n"),
+            (
+                "reordered",
+                "hw03-journal 4 4 1 rowcol top-left\n2 100 2 3\n1 101 2 2\n",
+            ),
+            (
+                "duplicate",
+                "hw03-journal 4 4 1 rowcol top-left\n1 100 2 3\n1 101 2 2\n",
+            ),
+        ] {
+            let path = journal_path(name);
+            std::fs::write(&path, journal).unwrap();
+            let err = read_journal(&path).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{}", name);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_corrupted_journal() {
+        let path = journal_path("corrupted");
+        std::fs::write(&path, "hw03-journal 4 4 1 rowcol top-left\n1 100 9 9\n").unwrap();
+        let err = read_journal(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,392 @@
+// Менеджер нескольких дисплеев.
+//
+// Команды подаются построчно. Строка `select <имя>` выбирает дисплей,
+// строка `display <имя> <ширина> <высота> <цвет>` создаёт новый,
+// строка `clear` очищает все дисплеи,
+// остальные строки - обычные числовые команды для выбранного дисплея.
+//
+// Ошибки изолированы: строка с неверной командой целиком не применяется к выбранному
+// дисплею, записывается в его собственный список ошибок и не затрагивает остальные дисплеи.
+//
+// Имя дисплея становится именем файла при экспорте, поэтому в нём запрещены
+// разделители путей и `..`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::command::{Command, ParseError, try_parse_commands};
+use crate::matrix::MAX_SIDE;
+use crate::{Display, DisplayError, create_display_with_coords, palette, try_apply_command};
+
+#[derive(Debug)]
+pub enum ManagerError {
+    UnknownDisplay(String),
+    DuplicateDisplay(String),
+    InvalidName(String),
+    NoDisplaySelected,
+    InvalidInput(String),
+    Parse { name: String, error: ParseError },
+    Display { name: String, error: DisplayError },
+}
+
+impl fmt::Display for ManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManagerError::UnknownDisplay(name) => write!(f, "Неизвестный дисплей: {}", name),
+            ManagerError::DuplicateDisplay(name) => write!(f, "Дисплей уже существует: {}", name),
+            ManagerError::InvalidName(name) => write!(f, "Недопустимое имя дисплея: {}", name),
+            ManagerError::NoDisplaySelected => write!(f, "Дисплей не выбран"),
+            ManagerError::InvalidInput(line) => write!(f, "Неверная строка: {}", line),
+            ManagerError::Parse { name, error } => write!(f, "{}: {}", name, error),
+            ManagerError::Display { name, error } => write!(f, "{}: {}", name, error),
+        }
+    }
+}
+
+struct ManagedDisplay {
+    display: Display,
+    errors: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct DisplayManager {
+    displays: BTreeMap<String, ManagedDisplay>,
+    selected: Option<String>,
+}
+
+impl DisplayManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: &str, display: Display) -> Result<(), ManagerError> {
+        if !is_valid_name(name) {
+            return Err(ManagerError::InvalidName(name.to_string()));
+        }
+        if self.displays.contains_key(name) {
+            return Err(ManagerError::DuplicateDisplay(name.to_string()));
+        }
+
+        self.displays.insert(
+            name.to_string(),
+            ManagedDisplay {
+                display,
+                errors: Vec::new(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn select(&mut self, name: &str) -> Result<(), ManagerError> {
+        if !self.displays.contains_key(name) {
+            return Err(ManagerError::UnknownDisplay(name.to_string()));
+        }
+
+        self.selected = Some(name.to_string());
+        Ok(())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.displays.keys().map(String::as_str)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Display> {
+        self.displays.get(name).map(|managed| &managed.display)
+    }
+
+    pub fn errors(&self, name: &str) -> &[String] {
+        self.displays
+            .get(name)
+            .map_or(&[], |managed| managed.errors.as_slice())
+    }
+
+    pub fn execute(&mut self, line: &str) -> Result<(), ManagerError> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match tokens.as_slice() {
+            [] => Ok(()),
+            ["select", name] => self.select(name),
+            // Возвращается первая ошибка, остальные остаются в списках ошибок дисплеев
+            ["clear"] => match self.clear_all().into_iter().next() {
+                Some(error) => Err(error),
+                None => Ok(()),
+            },
+            ["display", name, width, height, colour] => {
+                let parse = |value: &str| {
+                    value
+                        .parse()
+                        .map_err(|_| ManagerError::InvalidInput(line.to_string()))
+                };
+                let (width, height, colour) = (parse(width)?, parse(height)?, parse(colour)?);
+                let colour = u8::try_from(colour)
+                    .ok()
+                    .filter(|&colour| palette::is_ink(colour))
+                    .ok_or_else(|| ManagerError::InvalidInput(line.to_string()))?;
+                // Размер проверяется до создания: матрица выделяется сразу целиком
+                let side = 1..=MAX_SIDE;
+                if !side.contains(&width) || !side.contains(&height) {
+                    return Err(ManagerError::InvalidInput(line.to_string()));
+                }
+                let display = create_display_with_coords(width, height, colour, Default::default());
+                self.add(name, display)
+            }
+            _ => {
+                let numbers = tokens
+                    .iter()
+                    .map(|token| token.parse::<u64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| ManagerError::InvalidInput(line.to_string()))?;
+                self.apply_to_selected(&numbers)
+            }
+        }
+    }
+
+    // Выполняет все строки сценария; ошибки собираются, но не прерывают выполнение
+    pub fn run(&mut self, script: &str) -> Vec<ManagerError> {
+        script
+            .lines()
+            .filter_map(|line| self.execute(line).err())
+            .collect()
+    }
+
+    pub fn clear_all(&mut self) -> Vec<ManagerError> {
+        let mut errors = Vec::new();
+        for (name, managed) in self.displays.iter_mut() {
+            if let Err(error) = try_apply_command(&mut managed.display, Command::Clear) {
+                managed.errors.push(error.to_string());
+                errors.push(ManagerError::Display {
+                    name: name.clone(),
+                    error,
+                });
+            }
+        }
+        errors
+    }
+
+    // Сохраняет каждый дисплей в файл <имя>.txt в каталоге dir
+    pub fn export_all<P: AsRef<Path>>(&self, dir: P) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(&dir)?;
+
+        let mut paths = Vec::new();
+        for (name, managed) in &self.displays {
+            let path = dir.as_ref().join(format!("{}.txt", name));
+            fs::write(&path, managed.display.matrix.render())?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    fn apply_to_selected(&mut self, numbers: &[u64]) -> Result<(), ManagerError> {
+        let name = self
+            .selected
+            .clone()
+            .ok_or(ManagerError::NoDisplaySelected)?;
+        let managed = self
+            .displays
+            .get_mut(&name)
+            .ok_or_else(|| ManagerError::UnknownDisplay(name.clone()))?;
+
+        let commands = match try_parse_commands(numbers) {
+            Ok(commands) => commands,
+            Err(error) => {
+                managed.errors.push(error.to_string());
+                return Err(ManagerError::Parse { name, error });
+            }
+        };
+
+        // Строка применяется целиком или никак: при ошибке дисплей возвращается
+        // к снимку, а в журнал команды попадают только после успеха всей строки
+        let display = &mut managed.display;
+        let snapshot = (display.matrix.clone(), display.cursor, display.paint_mode);
+        let journal = display.journal.take();
+        let applied = commands
+            .iter()
+            .try_for_each(|&command| try_apply_command(display, command));
+        display.journal = journal;
+
+        let result = match applied {
+            Ok(()) => match display.journal.as_mut() {
+                Some(journal) => commands
+                    .iter()
+                    .try_for_each(|command| journal.record(command).map(drop))
+                    .map_err(DisplayError::Journal),
+                None => Ok(()),
+            },
+            Err(error) => {
+                (display.matrix, display.cursor, display.paint_mode) = snapshot;
+                Err(error)
+            }
+        };
+
+        result.map_err(|error| {
+            managed.errors.push(error.to_string());
+            ManagerError::Display { name, error }
+        })
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_display;
+    use crate::matrix::Matrix;
+
+    fn manager() -> DisplayManager {
+        let mut manager = DisplayManager::new();
+        manager.add("small", create_display(2, 2, 1)).unwrap();
+        manager.add("wide", create_display(5, 3, 2)).unwrap();
+        manager
+    }
+
+    #[test]
+    fn test_routes_commands() {
+        let mut manager = manager();
+        let errors = manager.run("select small\n1 1 1 2 3\nselect wide\n1 2 4 2 1\n");
+        assert!(errors.is_empty());
+
+        let mut small = Matrix::new(2, 2, 1);
+        small.set_colour(1, 1, 3);
+        assert_eq!(manager.get("small").unwrap().matrix, small);
+
+        let mut wide = Matrix::new(5, 3, 2);
+        wide.set_colour(2, 4, 1);
+        assert_eq!(manager.get("wide").unwrap().matrix, wide);
+    }
+
+    #[test]
+    fn test_display_directive() {
+        let mut manager = DisplayManager::new();
+        let errors = manager.run("display board 3 1 2\nselect board\n1 0 2 2 3\n");
+        assert!(errors.is_empty());
+        assert_eq!(manager.get("board").unwrap().matrix.colour(0, 2), 3);
+
+        let errors = manager.run("display board 3 1 2\n");
+        assert!(matches!(errors[..], [ManagerError::DuplicateDisplay(_)]));
+    }
+
+    #[test]
+    fn test_errors_are_isolated() {
+        let mut manager = manager();
+        let errors = manager.run("select small\n1 4 4 2 3\n1 0 0 2 3\nselect wide\n1 2 4 2 3\n");
+
+        // Команда за пределами маленького дисплея не мешает остальным строкам
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ManagerError::Display { name, error: DisplayError::OutOfBounds(4, 4) } if name == "small"
+        ));
+        assert_eq!(manager.errors("small").len(), 1);
+        assert!(manager.errors("wide").is_empty());
+
+        assert_eq!(manager.get("small").unwrap().matrix.colour(0, 0), 3);
+        assert_eq!(manager.get("wide").unwrap().matrix.colour(2, 4), 3);
+    }
+
+    #[test]
+    fn test_routing_errors() {
+        let mut manager = manager();
+        let errors = manager.run("1 0 0 2 3\nselect missing\nselect small\n2 x\n9\n");
+        assert!(matches!(
+            errors[..],
+            [
+                ManagerError::NoDisplaySelected,
+                ManagerError::UnknownDisplay(_),
+                ManagerError::InvalidInput(_),
+                ManagerError::Parse {
+                    error: ParseError::UnknownCommand(9),
+                    ..
+                },
+            ]
+        ));
+        assert_eq!(manager.errors("small").len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_display_directive() {
+        let mut manager = DisplayManager::new();
+        let errors = manager.run(
+            "display a 3 1 0\ndisplay b 3 1 4\ndisplay c 0 1 2\ndisplay d 3 0 2\ndisplay e 4097 1 2\n\
+             display f 1 4000000000 2\ndisplay ../x 3 1 2\n",
+        );
+        assert!(matches!(
+            errors[..],
+            [
+                ManagerError::InvalidInput(_),
+                ManagerError::InvalidInput(_),
+                ManagerError::InvalidInput(_),
+                ManagerError::InvalidInput(_),
+                ManagerError::InvalidInput(_),
+                ManagerError::InvalidInput(_),
+                ManagerError::InvalidName(_),
+            ]
+        ));
+        assert_eq!(manager.names().count(), 0);
+
+        for name in ["a/b", "a\\b", "..", ""] {
+            assert!(matches!(
+                manager.add(name, create_display(1, 1, 1)),
+                Err(ManagerError::InvalidName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_line_is_atomic() {
+        let mut manager = manager();
+        let errors = manager.run("select small\n1 1 1 2 3 3 3 1 9 9\n");
+
+        // Первые команды строки прошли, но строка откатилась целиком
+        assert!(matches!(
+            &errors[..],
+            [ManagerError::Display {
+                error: DisplayError::OutOfBounds(9, 9),
+                ..
+            }]
+        ));
+        let small = manager.get("small").unwrap();
+        assert_eq!(small.matrix, Matrix::new(2, 2, 1));
+        assert_eq!(small.cursor, (0, 0));
+        assert_eq!(small.paint_mode, Default::default());
+    }
+
+    #[test]
+    fn test_clear_directive() {
+        let mut manager = manager();
+        let errors = manager.run("select small\n1 1 1 2 3\nselect wide\n1 0 0 2 3\nclear\n");
+        assert!(errors.is_empty());
+        assert_eq!(manager.get("small").unwrap().matrix, Matrix::new(2, 2, 1));
+        assert_eq!(manager.get("wide").unwrap().matrix, Matrix::new(5, 3, 2));
+    }
+
+    #[test]
+    fn test_clear_all() {
+        let mut manager = manager();
+        manager.run("select small\n1 1 1 2 3\nselect wide\n1 0 0 2 3\n");
+        assert!(manager.clear_all().is_empty());
+        assert_eq!(manager.get("small").unwrap().matrix, Matrix::new(2, 2, 1));
+        assert_eq!(manager.get("wide").unwrap().matrix, Matrix::new(5, 3, 2));
+    }
+
+    #[test]
+    fn test_export_all() {
+        let mut manager = manager();
+        manager.run("select small\n1 1 1 2 3\n");
+
+        let dir = std::env::temp_dir().join(format!("hw03-export-{}", std::process::id()));
+        let paths = manager.export_all(&dir).unwrap();
+        assert_eq!(manager.names().collect::<Vec<_>>(), vec!["small", "wide"]);
+        assert_eq!(paths, vec![dir.join("small.txt"), dir.join("wide.txt")]);
+        assert_eq!(
+            fs::read_to_string(&paths[0]).unwrap(),
+            manager.get("small").unwrap().matrix.render()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,14 +1,15 @@
-#[derive(PartialEq, Debug)]
-pub struct Matrix(Vec<Vec<u8>>);
+use crate::palette;
 
-fn color_to_char(color: u8) -> char {
-    match color {
-        1 => '\u{1F534}', // Красный кружок
-        2 => '\u{1F7E2}', // Зелёный кружок
-        3 => '\u{1F535}', // Синий кружок
-        _ => ' ',
-    }
-}
+mod svg;
+pub use svg::SvgOptions;
+
+// Наибольшая ширина и высота дисплея из файла или сценария: без предела испорченный
+// журнал мог бы попросить матрицу, под которую не хватит памяти
+pub const MAX_SIDE: u32 = 4096;
+
+// Матрица хранится построчно: self.0[строка][столбец], строка 0 - верхняя
+#[derive(PartialEq, Debug, Clone)]
+pub struct Matrix(Vec<Vec<u8>>);
 
 impl Matrix {
     pub fn new(width: u32, height: u32, default_color: u8) -> Self {
@@ -16,15 +17,23 @@ impl Matrix {
     }
 
     pub fn display(&self) {
+        print!("{}", self.render());
+    }
+
+    pub fn render(&self) -> String {
+        let mut output = String::new();
         for row in &self.0 {
-            for &cell in row {
-                print!("{}", color_to_char(cell));
-            }
-            println!();
+            output.extend(row.iter().map(|&cell| palette::symbol(cell)));
+            output.push('\n');
         }
+        output
+    }
+
+    pub fn colour(&self, row: usize, col: usize) -> u8 {
+        self.0[row][col]
     }
 
-    pub fn set_colour(&mut self, x: u64, y: u64, colour: u8) {
-        self.0[x as usize][y as usize] = colour;
+    pub fn set_colour(&mut self, row: usize, col: usize, colour: u8) {
+        self.0[row][col] = colour;
     }
 }
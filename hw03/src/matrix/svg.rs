@@ -0,0 +1,229 @@
+// Отрисовка матрицы в SVG: один rect на каждую серию одинаковых цветов в строке,
+// по желанию - линии сетки и подписи координат в системе координат дисплея.
+
+use std::fmt::Write;
+
+use super::Matrix;
+use crate::coords::{Axes, CoordinateSystem};
+use crate::palette;
+
+pub struct SvgOptions {
+    // Размер одной ячейки в пикселях
+    pub cell_size: u32,
+    pub grid: bool,
+    pub labels: bool,
+    // Система координат, в которой подписываются строки и столбцы
+    pub coords: CoordinateSystem,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 20,
+            grid: false,
+            labels: false,
+            coords: CoordinateSystem::default(),
+        }
+    }
+}
+
+impl Matrix {
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let cell = options.cell_size as usize;
+        let height = self.0.len();
+        let width = self.0.first().map_or(0, Vec::len);
+
+        // Подписи занимают одну ячейку слева и сверху
+        let offset = if options.labels { cell } else { 0 };
+        let (svg_width, svg_height) = (offset + width * cell, offset + height * cell);
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = svg_width,
+            h = svg_height
+        )
+        .unwrap();
+
+        for (row, cells) in self.0.iter().enumerate() {
+            for (col, len, colour) in runs(cells) {
+                writeln!(
+                    svg,
+                    r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    offset + col * cell,
+                    offset + row * cell,
+                    len * cell,
+                    cell,
+                    fill(colour)
+                )
+                .unwrap();
+            }
+        }
+
+        if options.grid {
+            svg.push_str("  <g stroke=\"#808080\" stroke-width=\"1\">\n");
+            for row in 0..=height {
+                let y = offset + row * cell;
+                writeln!(
+                    svg,
+                    r#"    <line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                    offset, y, svg_width, y
+                )
+                .unwrap();
+            }
+            for col in 0..=width {
+                let x = offset + col * cell;
+                writeln!(
+                    svg,
+                    r#"    <line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                    x, offset, x, svg_height
+                )
+                .unwrap();
+            }
+            svg.push_str("  </g>\n");
+        }
+
+        if options.labels {
+            let coords = options.coords;
+            writeln!(
+                svg,
+                r#"  <g font-family="monospace" font-size="{}" text-anchor="middle" dominant-baseline="central">"#,
+                cell / 2
+            )
+            .unwrap();
+            for row in 0..height {
+                let (a, b) = coords.position(row, 0, height as u32);
+                let label = match coords.axes {
+                    Axes::RowCol => a,
+                    Axes::XY => b,
+                };
+                writeln!(
+                    svg,
+                    r#"    <text x="{}" y="{}">{}</text>"#,
+                    cell / 2,
+                    offset + row * cell + cell / 2,
+                    label
+                )
+                .unwrap();
+            }
+            for col in 0..width {
+                let (a, b) = coords.position(0, col, height as u32);
+                let label = match coords.axes {
+                    Axes::RowCol => b,
+                    Axes::XY => a,
+                };
+                writeln!(
+                    svg,
+                    r#"    <text x="{}" y="{}">{}</text>"#,
+                    offset + col * cell + cell / 2,
+                    cell / 2,
+                    label
+                )
+                .unwrap();
+            }
+            svg.push_str("  </g>\n");
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+// Серии одинаковых цветов в строке: (первый столбец, длина, цвет)
+fn runs(cells: &[u8]) -> Vec<(usize, usize, u8)> {
+    let mut runs: Vec<(usize, usize, u8)> = Vec::new();
+    for (col, &colour) in cells.iter().enumerate() {
+        match runs.last_mut() {
+            Some((_, len, last)) if *last == colour => *len += 1,
+            _ => runs.push((col, 1, colour)),
+        }
+    }
+    runs
+}
+
+fn fill(colour: u8) -> String {
+    match palette::rgb(colour) {
+        Some((r, g, b)) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        None => "none".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coords::Origin;
+
+    fn sample() -> Matrix {
+        // 4 столбца, 2 строки
+        let mut matrix = Matrix::new(4, 2, 1);
+        matrix.set_colour(0, 2, 3);
+        matrix.set_colour(1, 0, 2);
+        matrix.set_colour(1, 1, 2);
+        matrix
+    }
+
+    #[test]
+    fn test_runs() {
+        assert_eq!(runs(&[1, 1, 3, 1]), vec![(0, 2, 1), (2, 1, 3), (3, 1, 1)]);
+        assert_eq!(runs(&[2, 2, 2]), vec![(0, 3, 2)]);
+        assert!(runs(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_one_rect_per_run() {
+        let svg = sample().to_svg(&SvgOptions::default());
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"80\" height=\"40\"")
+        );
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect").count(), 5);
+        assert!(svg.contains(r##"<rect x="0" y="0" width="40" height="20" fill="#ff0000"/>"##));
+        assert!(svg.contains(r##"<rect x="40" y="0" width="20" height="20" fill="#0000ff"/>"##));
+        assert!(svg.contains(r##"<rect x="0" y="20" width="40" height="20" fill="#00ff00"/>"##));
+        assert!(!svg.contains("<line") && !svg.contains("<text"));
+    }
+
+    #[test]
+    fn test_grid() {
+        let options = SvgOptions {
+            grid: true,
+            ..SvgOptions::default()
+        };
+        let svg = sample().to_svg(&options);
+        // 3 горизонтальные и 5 вертикальных линий
+        assert_eq!(svg.matches("<line").count(), 8);
+        assert!(svg.contains(r#"<line x1="0" y1="40" x2="80" y2="40"/>"#));
+    }
+
+    #[test]
+    fn test_labels_row_col() {
+        let options = SvgOptions {
+            labels: true,
+            ..SvgOptions::default()
+        };
+        let svg = sample().to_svg(&options);
+        assert!(svg.contains("width=\"100\" height=\"60\""));
+        assert_eq!(svg.matches("<text").count(), 6);
+        // Подписи строк слева сверху вниз, подписи столбцов сверху
+        assert!(svg.contains(r#"<text x="10" y="30">0</text>"#));
+        assert!(svg.contains(r#"<text x="10" y="50">1</text>"#));
+        assert!(svg.contains(r#"<text x="90" y="10">3</text>"#));
+        assert!(svg.contains(r##"<rect x="20" y="20" width="40" height="20" fill="#ff0000"/>"##));
+    }
+
+    #[test]
+    fn test_labels_bottom_left() {
+        let options = SvgOptions {
+            labels: true,
+            coords: CoordinateSystem::new(Axes::XY, Origin::BottomLeft),
+            ..SvgOptions::default()
+        };
+        let svg = sample().to_svg(&options);
+        // Нижняя строка - y = 0, верхняя - y = 1
+        assert!(svg.contains(r#"<text x="10" y="30">1</text>"#));
+        assert!(svg.contains(r#"<text x="10" y="50">0</text>"#));
+        assert!(svg.contains(r#"<text x="30" y="10">0</text>"#));
+        assert!(svg.contains(r#"<text x="90" y="10">3</text>"#));
+    }
+}
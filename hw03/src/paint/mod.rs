@@ -0,0 +1,76 @@
+// Режимы рисования: как новый цвет сочетается с текущим цветом пикселя.
+// Смешанный цвет считается в RGB и затем квантуется к ближайшему цвету палитры.
+
+use crate::palette;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PaintMode {
+    // Цвет пикселя заменяется новым
+    #[default]
+    Replace,
+    // Новый цвет накладывается с непрозрачностью в процентах (0..=100)
+    Alpha(u8),
+    // Побитовое исключающее ИЛИ каналов RGB
+    Xor,
+    // Пиксель перекрашивается, только если он цвета фона
+    OnlyIfBackground,
+}
+
+impl PaintMode {
+    pub fn apply(self, current: u8, colour: u8, background: u8) -> u8 {
+        match self {
+            PaintMode::Replace => colour,
+            PaintMode::Alpha(opacity) => blend(current, colour, |a, b| {
+                let opacity = opacity as u32;
+                ((a as u32 * (100 - opacity) + b as u32 * opacity + 50) / 100) as u8
+            }),
+            PaintMode::Xor => blend(current, colour, |a, b| a ^ b),
+            PaintMode::OnlyIfBackground if current == background => colour,
+            PaintMode::OnlyIfBackground => current,
+        }
+    }
+}
+
+fn blend(current: u8, colour: u8, channel: impl Fn(u8, u8) -> u8) -> u8 {
+    // Пиксель вне палитры смешивать не с чем - просто закрашиваем его
+    let (Some(a), Some(b)) = (palette::rgb(current), palette::rgb(colour)) else {
+        return colour;
+    };
+
+    palette::nearest((channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace() {
+        assert_eq!(PaintMode::Replace.apply(1, 3, 1), 3);
+    }
+
+    #[test]
+    fn test_alpha() {
+        assert_eq!(PaintMode::Alpha(100).apply(1, 3, 1), 3);
+        assert_eq!(PaintMode::Alpha(0).apply(1, 3, 1), 1);
+        assert_eq!(PaintMode::Alpha(10).apply(1, 3, 1), 1);
+        // красный + зелёный пополам = (128, 128, 0), ближайший - коричневый
+        assert_eq!(PaintMode::Alpha(50).apply(1, 2, 1), 7);
+    }
+
+    #[test]
+    fn test_xor() {
+        // красный ^ зелёный = жёлтый
+        assert_eq!(PaintMode::Xor.apply(1, 2, 1), 4);
+        // красный ^ синий = фиолетовый
+        assert_eq!(PaintMode::Xor.apply(1, 3, 1), 5);
+        // цвет ^ тот же цвет = чёрный
+        assert_eq!(PaintMode::Xor.apply(3, 3, 1), 8);
+    }
+
+    #[test]
+    fn test_only_if_background() {
+        assert_eq!(PaintMode::OnlyIfBackground.apply(1, 3, 1), 3);
+        assert_eq!(PaintMode::OnlyIfBackground.apply(2, 3, 1), 2);
+    }
+}
@@ -0,0 +1,75 @@
+// Палитра дисплея: номер цвета, его RGB-значение и символ для вывода.
+//
+// Командами можно рисовать только основными цветами 1..=3, остальные цвета
+// появляются в результате смешивания и нужны, чтобы было куда квантовать результат.
+
+pub type Rgb = (u8, u8, u8);
+
+const PALETTE: [(u8, Rgb, char); 9] = [
+    (1, (255, 0, 0), '\u{1F534}'),    // Красный кружок
+    (2, (0, 255, 0), '\u{1F7E2}'),    // Зелёный кружок
+    (3, (0, 0, 255), '\u{1F535}'),    // Синий кружок
+    (4, (255, 255, 0), '\u{1F7E1}'),  // Жёлтый кружок
+    (5, (255, 0, 255), '\u{1F7E3}'),  // Фиолетовый кружок
+    (6, (255, 128, 0), '\u{1F7E0}'),  // Оранжевый кружок
+    (7, (128, 64, 0), '\u{1F7E4}'),   // Коричневый кружок
+    (8, (0, 0, 0), '\u{26AB}'),       // Чёрный кружок
+    (9, (255, 255, 255), '\u{26AA}'), // Белый кружок
+];
+
+pub fn is_ink(colour: u8) -> bool {
+    (1..=3).contains(&colour)
+}
+
+pub fn rgb(colour: u8) -> Option<Rgb> {
+    PALETTE
+        .iter()
+        .find(|(code, _, _)| *code == colour)
+        .map(|(_, rgb, _)| *rgb)
+}
+
+pub fn symbol(colour: u8) -> char {
+    PALETTE
+        .iter()
+        .find(|(code, _, _)| *code == colour)
+        .map_or(' ', |(_, _, symbol)| *symbol)
+}
+
+// Ближайший к rgb цвет палитры (по евклидову расстоянию)
+pub fn nearest(rgb: Rgb) -> u8 {
+    let distance = |other: Rgb| {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(rgb.0, other.0) + d(rgb.1, other.1) + d(rgb.2, other.2)
+    };
+
+    PALETTE
+        .iter()
+        .min_by_key(|(_, other, _)| distance(*other))
+        .map(|(code, _, _)| *code)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ink_colours() {
+        assert!(is_ink(1) && is_ink(2) && is_ink(3));
+        assert!(!is_ink(0) && !is_ink(4));
+    }
+
+    #[test]
+    fn test_nearest_exact() {
+        for (code, rgb, _) in PALETTE {
+            assert_eq!(nearest(rgb), code);
+        }
+    }
+
+    #[test]
+    fn test_nearest_quantizes() {
+        assert_eq!(nearest((250, 10, 5)), 1);
+        assert_eq!(nearest((20, 20, 20)), 8);
+        assert_eq!(nearest((240, 240, 10)), 4);
+    }
+}
@@ -1,75 +1,69 @@
+// Сам буфер живёт в общем крейте ring_buffer, здесь - байтовый интерфейс из задания
+type RingBuffer = ring_buffer::RingBuffer<u8>;
+
 #[allow(dead_code)]
-struct RingBuffer {
-    read_idx: usize,
-    write_idx: usize,
-    is_full: bool,
-    data: Vec<u8>,
+fn create(size: usize) -> RingBuffer {
+    RingBuffer::new(size)
 }
 
+// Буфер удваивается при заполнении, но не больше чем до max байт
 #[allow(dead_code)]
-fn create(size: usize) -> RingBuffer {
-    RingBuffer {
-        read_idx: 0,
-        write_idx: 0,
-        is_full: false,
-        data: vec![0; size],
-    }
+fn create_growable(size: usize, max: usize) -> RingBuffer {
+    RingBuffer::with_policy(size, ring_buffer::OverflowPolicy::Grow { max })
 }
 
 #[allow(dead_code)]
 fn write(rb: &mut RingBuffer, buf: &[u8]) -> usize {
-    if rb.is_full {
-        return 0;
-    }
-
-    let capacity = rb.data.len();
-
-    let len: usize = if !rb.is_full {
-        (rb.write_idx + capacity - rb.read_idx) % capacity
-    } else {
-        capacity
-    };
-
-    let free_space = rb.data.len() - len;
-    let bytes_to_write = std::cmp::min(buf.len(), free_space);
-
-    for element in buf.iter().take(bytes_to_write) {
-        rb.data[rb.write_idx] = *element;
-        rb.write_idx = (rb.write_idx + 1) % capacity;
-    }
-
-    if rb.write_idx == rb.read_idx {
-        rb.is_full = true
-    }
-
-    bytes_to_write
+    rb.write(buf)
 }
 
+// Читает не больше b байт: если в буфере меньше, возвращается только то, что есть
 #[allow(dead_code)]
 fn read(rb: &mut RingBuffer, b: usize) -> Vec<u8> {
-    let mut result = vec![0; b];
-    let capacity = rb.data.len();
+    rb.read(b)
+}
 
-    for element in result.iter_mut().take(b) {
-        *element = rb.data[rb.read_idx];
-        rb.read_idx = (rb.read_idx + 1) % capacity;
-    }
+// Количество байт, записанных в буфер и ещё не прочитанных
+#[allow(dead_code)]
+fn len(rb: &RingBuffer) -> usize {
+    rb.len()
+}
 
-    rb.is_full = false;
+#[allow(dead_code)]
+fn is_empty(rb: &RingBuffer) -> bool {
+    rb.is_empty()
+}
 
-    result
+// Сколько байт ещё можно записать
+#[allow(dead_code)]
+fn available(rb: &RingBuffer) -> usize {
+    rb.available()
+}
+
+#[allow(dead_code)]
+fn capacity(rb: &RingBuffer) -> usize {
+    rb.capacity()
 }
 
+// Старые тесты из задания оставлены как есть, поэтому часть замечаний clippy к ним
+// отключена, а модуль стоит на исходном месте, перед main
+#[allow(
+    clippy::items_after_test_module,
+    clippy::byte_char_slices,
+    unused_variables
+)]
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::VecDeque;
 
     // обязательно добавьте тесты
     #[test]
     fn test_create_buffer() {
         let rb = create(3);
-        assert_eq!(rb.data.capacity(), 3);
-        assert!(!rb.is_full);
+        assert_eq!(capacity(&rb), 3);
+        assert!(!rb.is_full());
     }
     #[test]
     fn test_simple_write() {
@@ -84,7 +78,7 @@ mod tests {
         let mut rb: RingBuffer = create(3);
         let b = write(&mut rb, &[b'a', b'b', b'c']);
         assert_eq!(b, 3);
-        assert!(rb.is_full);
+        assert!(rb.is_full());
     }
 
     #[test]
@@ -93,7 +87,7 @@ mod tests {
         let b = write(&mut rb, &[b'a', b'b']);
         let d = read(&mut rb, 1);
         assert_eq!(d, vec![b'a']);
-        assert_eq!(rb.read_idx, 1);
+        assert_eq!(rb.read_idx(), 1);
     }
 
     #[test]
@@ -103,9 +97,9 @@ mod tests {
         let d = read(&mut rb, 1);
 
         assert_eq!(d, vec![b'a']);
-        assert_eq!(rb.read_idx, 1);
-        assert_eq!(rb.write_idx, 0);
-        assert!(!rb.is_full);
+        assert_eq!(rb.read_idx(), 1);
+        assert_eq!(rb.write_idx(), 0);
+        assert!(!rb.is_full());
     }
 
     #[test]
@@ -114,32 +108,124 @@ mod tests {
 
         let mut b = write(&mut rb, &[b'a', b'b']);
         assert_eq!(b, 2);
-        assert_eq!(rb.write_idx, 2);
-        println!("{:?}", rb.data);
+        assert_eq!(rb.write_idx(), 2);
+        println!("{:?}", rb);
 
         b = write(&mut rb, &[b'c', b'd']);
         assert_eq!(b, 1);
-        assert_eq!(rb.write_idx, 0);
-        assert!(rb.is_full);
-        println!("{:?}", rb.data);
+        assert_eq!(rb.write_idx(), 0);
+        assert!(rb.is_full());
+        println!("{:?}", rb);
 
         let d = read(&mut rb, 1);
         assert_eq!(d, vec![b'a']);
-        assert_eq!(rb.read_idx, 1);
-        assert!(!rb.is_full);
-        println!("{:?}", rb.data);
+        assert_eq!(rb.read_idx(), 1);
+        assert!(!rb.is_full());
+        println!("{:?}", rb);
 
         b = write(&mut rb, &[b'e']);
         assert_eq!(b, 1);
-        assert_eq!(rb.write_idx, 1);
-        assert!(rb.is_full);
-        println!("{:?}", rb.data);
+        assert_eq!(rb.write_idx(), 1);
+        assert!(rb.is_full());
+        println!("{:?}", rb);
 
         let d = read(&mut rb, 2);
         assert_eq!(d, vec![b'b', b'c']);
-        assert_eq!(rb.read_idx, 0);
-        assert!(!rb.is_full);
-        println!("{:?}", rb.data);
+        assert_eq!(rb.read_idx(), 0);
+        assert!(!rb.is_full());
+        println!("{:?}", rb);
+    }
+
+    #[test]
+    fn test_growable_buffer() {
+        let mut rb = create_growable(2, 5);
+        assert_eq!(write(&mut rb, b"abc"), 3);
+        assert_eq!(capacity(&rb), 4);
+        assert_eq!(write(&mut rb, b"def"), 2);
+        assert_eq!(capacity(&rb), 5);
+        assert_eq!(read(&mut rb, 5), b"abcde");
+    }
+
+    #[test]
+    fn test_read_more_than_stored() {
+        let mut rb: RingBuffer = create(3);
+        write(&mut rb, b"ab");
+
+        let d = read(&mut rb, 3);
+        assert_eq!(d, vec![b'a', b'b']);
+        assert_eq!(rb.read_idx(), 2);
+        assert_eq!(rb.write_idx(), 2);
+        assert!(is_empty(&rb));
+
+        assert_eq!(read(&mut rb, 1), vec![]);
+        assert_eq!(rb.read_idx(), 2);
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut rb: RingBuffer = create(3);
+        assert_eq!(capacity(&rb), 3);
+        assert_eq!(len(&rb), 0);
+        assert_eq!(available(&rb), 3);
+        assert!(is_empty(&rb));
+
+        write(&mut rb, b"abc");
+        assert_eq!(len(&rb), 3);
+        assert_eq!(available(&rb), 0);
+
+        read(&mut rb, 2);
+        write(&mut rb, b"d");
+        assert_eq!(len(&rb), 2);
+        assert_eq!(available(&rb), 1);
+        assert!(!is_empty(&rb));
+    }
+
+    #[test]
+    fn test_write_empty_slice() {
+        let mut rb: RingBuffer = create(3);
+        assert_eq!(write(&mut rb, &[]), 0);
+        assert!(!rb.is_full());
+        assert_eq!(available(&rb), 3);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Write(Vec<u8>),
+        Read(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            prop::collection::vec(any::<u8>(), 0..8).prop_map(Op::Write),
+            (0..8usize).prop_map(Op::Read),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_vec_deque(size in 1..8usize, ops in prop::collection::vec(op(), 0..64)) {
+            let mut rb = create(size);
+            let mut model = VecDeque::new();
+
+            for op in ops {
+                match op {
+                    Op::Write(buf) => {
+                        let written = write(&mut rb, &buf);
+                        let expected = buf.len().min(size - model.len());
+                        model.extend(&buf[..expected]);
+                        prop_assert_eq!(written, expected);
+                    }
+                    Op::Read(b) => {
+                        let expected: Vec<u8> = model.drain(..b.min(model.len())).collect();
+                        prop_assert_eq!(read(&mut rb, b), expected);
+                    }
+                }
+                prop_assert_eq!(len(&rb), model.len());
+                prop_assert_eq!(is_empty(&rb), model.is_empty());
+                prop_assert_eq!(available(&rb), size - model.len());
+                prop_assert_eq!(capacity(&rb), size);
+            }
+        }
     }
 }
 
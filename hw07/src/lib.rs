@@ -1,80 +1,82 @@
+pub use ring_buffer::OverflowPolicy;
+
 #[derive(Debug, PartialEq)]
 pub enum WriteError {
     NoSpaceLeft,
 }
 
-pub struct RingBuffer {
-    read_idx: usize,
-    write_idx: usize,
-    is_full: bool,
-    data: Vec<u8>,
-}
+// Сам буфер живёт в общем крейте ring_buffer, здесь - строковый интерфейс из задания.
+// Буфер текстовый: символы UTF-8 записываются и читаются только целиком.
+pub type RingBuffer = ring_buffer::TextRingBuffer;
 
 pub fn create(size: usize) -> RingBuffer {
-    RingBuffer {
-        read_idx: 0,
-        write_idx: 0,
-        is_full: false,
-        data: vec![0; size],
-    }
+    RingBuffer::new(size)
+}
+
+// В режиме OverwriteOldest запись в полный буфер вытесняет самые старые символы
+pub fn create_with_policy(size: usize, policy: OverflowPolicy) -> RingBuffer {
+    RingBuffer::with_policy(size, policy)
 }
 
 pub fn write(rb: &mut RingBuffer, buf: &str) -> Result<usize, WriteError> {
-    if rb.is_full {
+    if rb.is_full() && rb.policy() == OverflowPolicy::Reject {
         return Err(WriteError::NoSpaceLeft);
     }
 
-    let buf = buf.as_bytes();
-
-    let capacity = rb.data.len();
-
-    let len: usize = if !rb.is_full {
-        (rb.write_idx + capacity - rb.read_idx) % capacity
-    } else {
-        capacity
-    };
-
-    let free_space = rb.data.len() - len;
-    let bytes_to_write = std::cmp::min(buf.len(), free_space);
-
-    for element in buf.iter().take(bytes_to_write) {
-        rb.data[rb.write_idx] = *element;
-        rb.write_idx = (rb.write_idx + 1) % capacity;
-    }
-
-    if rb.write_idx == rb.read_idx {
-        rb.is_full = true
+    // Если не поместился ни один символ, считаем, что места нет
+    let written = rb.write(buf);
+    if written.bytes == 0 && !buf.is_empty() {
+        return Err(WriteError::NoSpaceLeft);
     }
 
-    Ok(bytes_to_write)
+    Ok(written.bytes)
 }
 
+// Читает не больше b байт: если в буфере меньше, возвращается только то, что есть.
+// Символ никогда не разрезается: если первый символ длиннее b байт (и b > 0),
+// он возвращается целиком, чтобы чтение в цикле всегда продвигалось.
 pub fn read(rb: &mut RingBuffer, b: usize) -> Option<String> {
-    if rb.read_idx == rb.write_idx && !rb.is_full {
+    if rb.is_empty() {
         return None;
     }
-    let mut result = vec![0; b];
-    let capacity = rb.data.len();
 
-    for element in result.iter_mut().take(b) {
-        *element = rb.data[rb.read_idx];
-        rb.read_idx = (rb.read_idx + 1) % capacity;
-    }
+    Some(rb.read(b))
+}
 
-    rb.is_full = false;
+// Количество байт, записанных в буфер и ещё не прочитанных
+pub fn len(rb: &RingBuffer) -> usize {
+    rb.len()
+}
+
+pub fn is_empty(rb: &RingBuffer) -> bool {
+    rb.is_empty()
+}
+
+// Сколько байт ещё можно записать
+pub fn available(rb: &RingBuffer) -> usize {
+    rb.available()
+}
+
+pub fn capacity(rb: &RingBuffer) -> usize {
+    rb.capacity()
+}
 
-    Some(String::from_utf8_lossy(&result).to_string())
+// Сколько байт было потеряно из-за перезаписи
+pub fn dropped(rb: &RingBuffer) -> u64 {
+    rb.dropped()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::VecDeque;
 
     #[test]
     fn test_create_buffer() {
         let rb = create(3);
-        assert_eq!(rb.data.capacity(), 3);
-        assert!(!rb.is_full);
+        assert_eq!(capacity(&rb), 3);
+        assert!(!rb.is_full());
     }
 
     #[test]
@@ -90,7 +92,7 @@ mod tests {
         let mut rb: RingBuffer = create(3);
         let b = write(&mut rb, "abc");
         assert_eq!(b, Ok(3));
-        assert!(rb.is_full);
+        assert!(rb.is_full());
     }
 
     #[test]
@@ -99,7 +101,7 @@ mod tests {
         let _b = write(&mut rb, "ab");
         let d = read(&mut rb, 1);
         assert_eq!(d, Some("a".to_string()));
-        assert_eq!(rb.read_idx, 1);
+        assert_eq!(rb.read_idx(), 1);
     }
 
     #[test]
@@ -109,9 +111,9 @@ mod tests {
         let d = read(&mut rb, 1);
 
         assert_eq!(d, Some("a".to_string()));
-        assert_eq!(rb.read_idx, 1);
-        assert_eq!(rb.write_idx, 0);
-        assert!(!rb.is_full);
+        assert_eq!(rb.read_idx(), 1);
+        assert_eq!(rb.write_idx(), 0);
+        assert!(!rb.is_full());
     }
 
     #[test]
@@ -120,27 +122,27 @@ mod tests {
 
         let mut b = write(&mut rb, "ab");
         assert_eq!(b, Ok(2));
-        assert_eq!(rb.write_idx, 2);
+        assert_eq!(rb.write_idx(), 2);
 
         b = write(&mut rb, "cd");
         assert_eq!(b, Ok(1));
-        assert_eq!(rb.write_idx, 0);
-        assert!(rb.is_full);
+        assert_eq!(rb.write_idx(), 0);
+        assert!(rb.is_full());
 
         let d = read(&mut rb, 1);
         assert_eq!(d, Some("a".to_string()));
-        assert_eq!(rb.read_idx, 1);
-        assert!(!rb.is_full);
+        assert_eq!(rb.read_idx(), 1);
+        assert!(!rb.is_full());
 
         b = write(&mut rb, "e");
         assert_eq!(b, Ok(1));
-        assert_eq!(rb.write_idx, 1);
-        assert!(rb.is_full);
+        assert_eq!(rb.write_idx(), 1);
+        assert!(rb.is_full());
 
         let d = read(&mut rb, 2);
         assert_eq!(d, Some("bc".to_string()));
-        assert_eq!(rb.read_idx, 0);
-        assert!(!rb.is_full);
+        assert_eq!(rb.read_idx(), 0);
+        assert!(!rb.is_full());
     }
 
     #[test]
@@ -159,4 +161,134 @@ mod tests {
 
         assert_eq!(b, Err(WriteError::NoSpaceLeft))
     }
+
+    #[test]
+    fn test_read_more_than_stored() {
+        let mut rb = create(3);
+        let _ = write(&mut rb, "ab");
+
+        let d = read(&mut rb, 3);
+        assert_eq!(d, Some("ab".to_string()));
+        assert_eq!(rb.read_idx(), 2);
+        assert_eq!(rb.write_idx(), 2);
+        assert!(is_empty(&rb));
+
+        assert_eq!(read(&mut rb, 1), None);
+        assert_eq!(rb.read_idx(), 2);
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut rb = create(3);
+        assert_eq!(capacity(&rb), 3);
+        assert_eq!(len(&rb), 0);
+        assert_eq!(available(&rb), 3);
+        assert!(is_empty(&rb));
+
+        let _ = write(&mut rb, "abc");
+        assert_eq!(len(&rb), 3);
+        assert_eq!(available(&rb), 0);
+
+        read(&mut rb, 2);
+        let _ = write(&mut rb, "d");
+        assert_eq!(len(&rb), 2);
+        assert_eq!(available(&rb), 1);
+        assert!(!is_empty(&rb));
+    }
+
+    #[test]
+    fn test_write_empty_str() {
+        let mut rb = create(3);
+        assert_eq!(write(&mut rb, ""), Ok(0));
+        assert!(!rb.is_full());
+        assert_eq!(read(&mut rb, 1), None);
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut rb = create_with_policy(3, OverflowPolicy::OverwriteOldest);
+        assert_eq!(write(&mut rb, "abc"), Ok(3));
+        assert_eq!(write(&mut rb, "d"), Ok(1));
+        assert_eq!(dropped(&rb), 1);
+        assert_eq!(rb.read_idx(), 1);
+        assert_eq!(read(&mut rb, 3), Some("bcd".to_string()));
+    }
+
+    #[test]
+    fn test_cyrillic() {
+        let mut rb = create(5);
+        assert_eq!(write(&mut rb, "ёжик"), Ok(4));
+        assert_eq!(write(&mut rb, "ы"), Err(WriteError::NoSpaceLeft));
+        assert_eq!(read(&mut rb, 3), Some("ё".to_string()));
+        assert_eq!(read(&mut rb, 1), Some("ж".to_string()));
+        assert_eq!(read(&mut rb, 2), None);
+    }
+
+    #[test]
+    fn test_byte_read_loop_ends() {
+        let mut rb = create(8);
+        write(&mut rb, "я🦀a").unwrap();
+        let mut text = String::new();
+        while let Some(chunk) = read(&mut rb, 1) {
+            text.push_str(&chunk);
+        }
+        assert_eq!(text, "я🦀a");
+    }
+
+    #[test]
+    fn test_emoji() {
+        let mut rb = create(6);
+        assert_eq!(write(&mut rb, "🦀🦀"), Ok(4));
+        assert_eq!(write(&mut rb, "a🦀"), Ok(1));
+        assert_eq!(read(&mut rb, 6), Some("🦀a".to_string()));
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Write(String),
+        Read(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            "[a-z]{0,8}".prop_map(Op::Write),
+            (0..8usize).prop_map(Op::Read),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_vec_deque(size in 1..8usize, ops in prop::collection::vec(op(), 0..64)) {
+            let mut rb = create(size);
+            let mut model = VecDeque::new();
+
+            for op in ops {
+                match op {
+                    Op::Write(buf) => {
+                        let result = write(&mut rb, &buf);
+                        if model.len() == size {
+                            prop_assert_eq!(result, Err(WriteError::NoSpaceLeft));
+                        } else {
+                            let expected = buf.len().min(size - model.len());
+                            model.extend(&buf.as_bytes()[..expected]);
+                            prop_assert_eq!(result, Ok(expected));
+                        }
+                    }
+                    Op::Read(b) => {
+                        let expected = if model.is_empty() {
+                            None
+                        } else {
+                            let bytes: Vec<u8> = model.drain(..b.min(model.len())).collect();
+                            Some(String::from_utf8(bytes).unwrap())
+                        };
+                        prop_assert_eq!(read(&mut rb, b), expected);
+                    }
+                }
+                prop_assert_eq!(len(&rb), model.len());
+                prop_assert_eq!(is_empty(&rb), model.is_empty());
+                prop_assert_eq!(available(&rb), size - model.len());
+                prop_assert_eq!(capacity(&rb), size);
+            }
+        }
+    }
 }
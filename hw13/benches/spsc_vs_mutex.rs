@@ -0,0 +1,77 @@
+// Сравнение пропускной способности: один писатель и один читатель передают
+// одинаковый объём данных через ThreadSafeRingBuffer (Mutex + Condvar)
+// и через SPSC-буфер без блокировок.
+//
+// Запуск: cargo bench -p hw13
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hw13::ThreadSafeRingBuffer;
+use ring_buffer::spsc;
+
+const TOTAL: usize = 16 * 1024 * 1024;
+const CAPACITY: usize = 4096;
+
+fn mutex(chunk: usize) -> Duration {
+    let tsrb = ThreadSafeRingBuffer::new(CAPACITY);
+    let data = "x".repeat(chunk);
+    let start = Instant::now();
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            let mut sent = 0;
+            while sent < TOTAL {
+                sent += tsrb.write_blocking(&data).unwrap();
+            }
+        });
+
+        let mut received = 0;
+        while received < TOTAL {
+            received += tsrb.read_blocking(chunk).unwrap().len();
+        }
+    });
+
+    start.elapsed()
+}
+
+fn lock_free(chunk: usize) -> Duration {
+    let (mut producer, mut consumer) = spsc::ring(CAPACITY);
+    let data = vec![b'x'; chunk];
+    let start = Instant::now();
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            let mut sent = 0;
+            while sent < TOTAL {
+                match producer.write(&data) {
+                    0 => thread::yield_now(),
+                    written => sent += written,
+                }
+            }
+        });
+
+        let mut received = 0;
+        while received < TOTAL {
+            match consumer.read(chunk).len() {
+                0 => thread::yield_now(),
+                read => received += read,
+            }
+        }
+    });
+
+    start.elapsed()
+}
+
+fn main() {
+    println!("{:>6} {:>12} {:>12}", "chunk", "mutex MB/s", "spsc MB/s");
+    for chunk in [1, 16, 256] {
+        let mb_per_sec = |elapsed: Duration| TOTAL as f64 / elapsed.as_secs_f64() / 1e6;
+        println!(
+            "{:>6} {:>12.1} {:>12.1}",
+            chunk,
+            mb_per_sec(mutex(chunk)),
+            mb_per_sec(lock_free(chunk))
+        );
+    }
+}
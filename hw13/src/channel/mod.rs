@@ -0,0 +1,356 @@
+// Канал поверх ThreadSafeRingBuffer в духе std::sync::mpsc::sync_channel: Sender
+// только пишет, Receiver только читает, и обе половины можно клонировать.
+//
+// Когда удаляется последний Sender, буфер закрывается: получатели дочитывают остатки
+// и получают Disconnected. Когда удаляется последний Receiver, буфер тоже закрывается,
+// и отправители сразу узнают, что их никто не слушает.
+//
+// Каждая отправка - отдельное сообщение: в буфер пишется кадр "<длина в байтах>:<строка>",
+// целиком и под одной блокировкой, а recv под одной блокировкой забирает ровно один кадр.
+// Поэтому сообщения разных отправителей не перемешиваются, а ёмкость канала считается
+// в байтах вместе с заголовками кадров. Двоичный varint-заголовок из ring_buffer::frame
+// здесь не подходит: буфер текстовый и принимает только UTF-8.
+//
+// Если кадр в начале буфера всё же не разбирается, recv возвращает Corrupt, а не
+// пустую строку: границу следующего сообщения в испорченных данных не найти.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{RingBuffer, ThreadSafeRingBuffer, WriteError, Writer};
+
+#[derive(Debug, PartialEq)]
+pub enum SendError {
+    // Все получатели удалены
+    Disconnected,
+    // Кадр с сообщением больше всего буфера и не поместится никогда
+    NoSpaceLeft,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TrySendError {
+    // Кадр с сообщением сейчас не помещается
+    Full,
+    Disconnected,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RecvError {
+    // Все отправители удалены и данные прочитаны
+    Disconnected,
+    // Кадр в начале буфера не разбирается
+    Corrupt,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+    Corrupt,
+}
+
+#[derive(Clone)]
+pub struct Sender {
+    writer: Writer,
+}
+
+pub struct Receiver {
+    tsrb: ThreadSafeRingBuffer,
+    receivers: Arc<AtomicUsize>,
+}
+
+// Создаёт канал на capacity байт
+pub fn ring_channel(capacity: usize) -> (Sender, Receiver) {
+    let tsrb = ThreadSafeRingBuffer::new(capacity);
+    (
+        Sender {
+            writer: tsrb.writer(),
+        },
+        Receiver {
+            tsrb,
+            receivers: Arc::new(AtomicUsize::new(1)),
+        },
+    )
+}
+
+impl Sender {
+    // Ждёт, пока кадр с сообщением не поместится в буфер целиком, и записывает его
+    pub fn send(&self, s: &str) -> Result<(), SendError> {
+        let frame = frame(s);
+        match self.writer.tsrb.write_when_fits(&frame, frame.len(), None) {
+            Ok(_) => Ok(()),
+            Err(WriteError::Closed) => Err(SendError::Disconnected),
+            Err(_) => Err(SendError::NoSpaceLeft),
+        }
+    }
+
+    // Не ждёт: сообщение записывается целиком или не записывается вовсе
+    pub fn try_send(&self, s: &str) -> Result<(), TrySendError> {
+        let frame = frame(s);
+        let tsrb = &self.writer.tsrb;
+        let mut state = tsrb.lock();
+        if state.closed {
+            return Err(TrySendError::Disconnected);
+        }
+        if !state.writers.is_empty() || !state.rb.fits(frame.len()) {
+            return Err(TrySendError::Full);
+        }
+
+        state.rb.write(&frame).map_err(|_| TrySendError::Full)?;
+        tsrb.notify_readers();
+        Ok(())
+    }
+}
+
+impl Receiver {
+    // Ждёт и забирает одно сообщение
+    pub fn recv(&self) -> Result<String, RecvError> {
+        self.tsrb
+            .read_with(None, take_message)
+            .map_err(|_| RecvError::Disconnected)?
+            .ok_or(RecvError::Corrupt)
+    }
+
+    pub fn try_recv(&self) -> Result<String, TryRecvError> {
+        // Пустоту и закрытие проверяем под одной блокировкой, иначе между ними
+        // последний отправитель мог бы успеть что-то записать и удалиться
+        let mut state = self.tsrb.lock();
+        if !state.rb.is_empty() {
+            let message = take_message(&mut state.rb);
+            self.tsrb.notify_writers();
+            message.ok_or(TryRecvError::Corrupt)
+        } else if state.closed {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    // Ждущий итератор: заканчивается, когда все отправители удалены и данные прочитаны
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { receiver: self }
+    }
+}
+
+impl Clone for Receiver {
+    fn clone(&self) -> Self {
+        self.receivers.fetch_add(1, Ordering::Relaxed);
+        Receiver {
+            tsrb: self.tsrb.clone(),
+            receivers: self.receivers.clone(),
+        }
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        if self.receivers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.tsrb.close();
+        }
+    }
+}
+
+pub struct Iter<'a> {
+    receiver: &'a Receiver,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.receiver.recv().ok()
+    }
+}
+
+pub struct IntoIter {
+    receiver: Receiver,
+}
+
+impl Iterator for IntoIter {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl IntoIterator for Receiver {
+    type Item = String;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter { receiver: self }
+    }
+}
+
+impl<'a> IntoIterator for &'a Receiver {
+    type Item = String;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+fn frame(s: &str) -> String {
+    format!("{}:{}", s.len(), s)
+}
+
+// Кадры пишутся только целиком, поэтому в непустом буфере всегда лежит целый кадр.
+// None - если заголовок не разбирается или обещает больше, чем есть в буфере.
+fn take_message(rb: &mut RingBuffer) -> Option<String> {
+    let mut len: usize = 0;
+    let mut digits = 0;
+    loop {
+        match rb.inner.peek_char()? {
+            ':' if digits > 0 => break,
+            c => {
+                let digit = c.to_digit(10)? as usize;
+                len = len.checked_mul(10)?.checked_add(digit)?;
+                digits += 1;
+            }
+        }
+        rb.read(1);
+    }
+    rb.read(1);
+
+    if len > rb.len() {
+        return None;
+    }
+    let message = rb.read(len).unwrap_or_default();
+    // Длина, которая режет символ, тоже испорчена
+    (message.len() == len).then_some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::{scope, sleep};
+    use std::time::Duration;
+
+    #[test]
+    fn test_send_recv() {
+        let (tx, rx) = ring_channel(16);
+        assert_eq!(tx.send("ab"), Ok(()));
+        assert_eq!(tx.try_send("дом"), Ok(()));
+        // Кадр "3:xyz" в оставшиеся 4 байта не влезает даже частично
+        assert_eq!(tx.try_send("xyz"), Err(TrySendError::Full));
+        assert_eq!(rx.recv(), Ok("ab".to_string()));
+        assert_eq!(rx.recv(), Ok("дом".to_string()));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+        assert_eq!(tx.send("🦀"), Ok(()));
+        assert_eq!(tx.send(""), Ok(()));
+        assert_eq!(tx.send("1:2"), Ok(()));
+        assert_eq!(rx.try_recv(), Ok("🦀".to_string()));
+        assert_eq!(rx.try_recv(), Ok(String::new()));
+        assert_eq!(rx.try_recv(), Ok("1:2".to_string()));
+    }
+
+    #[test]
+    fn test_corrupt_frame() {
+        for garbage in ["x", ":ab", "5:ab", "1:ж", "99999999999999999999999:a"] {
+            let (_tx, rx) = ring_channel(32);
+            rx.tsrb.write(garbage).unwrap();
+            assert_eq!(rx.recv(), Err(RecvError::Corrupt), "{}", garbage);
+
+            let (_tx, rx) = ring_channel(32);
+            rx.tsrb.write(garbage).unwrap();
+            assert_eq!(rx.try_recv(), Err(TryRecvError::Corrupt), "{}", garbage);
+        }
+    }
+
+    #[test]
+    fn test_message_longer_than_buffer() {
+        let (tx, rx) = ring_channel(8);
+        assert_eq!(tx.send("кольцо"), Err(SendError::NoSpaceLeft));
+        assert_eq!(tx.try_send("кольцо"), Err(TrySendError::Full));
+        // Кадр "6:abcdef" занимает буфер ровно целиком
+        assert_eq!(tx.send("abcdef"), Ok(()));
+        assert_eq!(rx.recv(), Ok("abcdef".to_string()));
+    }
+
+    #[test]
+    fn test_senders_dropped() {
+        let (tx, rx) = ring_channel(8);
+        let tx2 = tx.clone();
+        tx.send("a").unwrap();
+        drop(tx);
+        assert_eq!(rx.try_recv(), Ok("a".to_string()));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+        tx2.send("b").unwrap();
+        drop(tx2);
+        assert_eq!(rx.recv(), Ok("b".to_string()));
+        assert_eq!(rx.recv(), Err(RecvError::Disconnected));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn test_receivers_dropped() {
+        let (tx, rx) = ring_channel(3);
+        let rx2 = rx.clone();
+        tx.send("a").unwrap();
+        drop(rx);
+        assert_eq!(tx.try_send("b"), Err(TrySendError::Full));
+
+        scope(|s| {
+            // Отправитель ждёт места и узнаёт, что получателей больше нет
+            let sender = s.spawn(|| tx.send("b"));
+            sleep(Duration::from_millis(20));
+            drop(rx2);
+            assert_eq!(sender.join().unwrap(), Err(SendError::Disconnected));
+        });
+        assert_eq!(tx.try_send("c"), Err(TrySendError::Disconnected));
+    }
+
+    #[test]
+    fn test_multithread_iter() {
+        let (tx, rx) = ring_channel(16);
+        let num_threads = 4;
+        let items_per_thread = 1000;
+
+        let received = scope(|s| {
+            for i in 0..num_threads {
+                let tx = tx.clone();
+                s.spawn(move || {
+                    for k in 0..items_per_thread {
+                        tx.send(&format!("{}-{}", i, k)).unwrap();
+                    }
+                });
+            }
+            drop(tx);
+
+            let readers: Vec<_> = (0..2)
+                .map(|_| {
+                    let rx = rx.clone();
+                    s.spawn(move || rx.into_iter().collect::<Vec<_>>())
+                })
+                .collect();
+            drop(rx);
+            readers
+                .into_iter()
+                .map(|reader| reader.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        // Каждое сообщение пришло целым, ровно один раз, и у каждого получателя
+        // сообщения одного отправителя идут в порядке отправки
+        let mut all = Vec::new();
+        for messages in &received {
+            let mut last = vec![None; num_threads];
+            for message in messages {
+                let (i, k) = message.split_once('-').unwrap();
+                let (i, k): (usize, usize) = (i.parse().unwrap(), k.parse().unwrap());
+                assert!(last[i] < Some(k));
+                last[i] = Some(k);
+                all.push((i, k));
+            }
+        }
+        all.sort();
+        let expected: Vec<_> = (0..num_threads)
+            .flat_map(|i| (0..items_per_thread).map(move |k| (i, k)))
+            .collect();
+        assert_eq!(all, expected);
+    }
+}
@@ -0,0 +1,236 @@
+// Асинхронный интерфейс ThreadSafeRingBuffer без зависимости от рантайма:
+// write_async и read_async возвращают написанные вручную Future.
+//
+// Если операцию нельзя выполнить сразу, Future кладёт Waker задачи в список буфера
+// и возвращает Pending. Буфер будит задачи из списка там же, где будит ждущие потоки:
+// читателей - после записи, писателей - после чтения, и всех - при закрытии.
+// Waker регистрируется, пока держится блокировка состояния, поэтому запись или чтение
+// из другого потока между проверкой и регистрацией не теряется.
+//
+// Асинхронный писатель не встаёт в очередь ждущих потоков, но и не обгоняет её:
+// пока в очереди кто-то есть, он ждёт.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll, Waker};
+
+use crate::{ReadError, ThreadSafeRingBuffer, WriteError};
+
+impl ThreadSafeRingBuffer {
+    // Асинхронный аналог write_blocking: ждёт места хотя бы под первый символ
+    pub fn write_async<'a>(&'a self, buf: &'a str) -> WriteFuture<'a> {
+        WriteFuture { tsrb: self, buf }
+    }
+
+    // Асинхронный аналог read_blocking: ждёт данных и читает не больше b байт
+    pub fn read_async(&self, b: usize) -> ReadFuture<'_> {
+        ReadFuture {
+            tsrb: self,
+            b,
+            counted: false,
+        }
+    }
+}
+
+pub struct WriteFuture<'a> {
+    tsrb: &'a ThreadSafeRingBuffer,
+    buf: &'a str,
+}
+
+impl Future for WriteFuture<'_> {
+    type Output = Result<usize, WriteError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let first = self.buf.chars().next().map_or(0, char::len_utf8);
+        let mut state = self.tsrb.lock();
+        if state.closed {
+            return Poll::Ready(Err(WriteError::Closed));
+        }
+        if first > state.rb.max_capacity() {
+            return Poll::Ready(Err(WriteError::NoSpaceLeft));
+        }
+
+        if state.writers.is_empty() && state.rb.fits(first) {
+            let written = state.rb.write(self.buf);
+            self.tsrb.notify_readers();
+            return Poll::Ready(written);
+        }
+
+        register(&self.tsrb.inner.write_wakers, cx.waker());
+        Poll::Pending
+    }
+}
+
+pub struct ReadFuture<'a> {
+    tsrb: &'a ThreadSafeRingBuffer,
+    b: usize,
+    // Ожидание уже учтено в метриках blocked_reads
+    counted: bool,
+}
+
+impl Future for ReadFuture<'_> {
+    type Output = Result<String, ReadError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let tsrb = self.tsrb;
+        let mut state = tsrb.lock();
+        if !state.rb.is_empty() {
+            let read = state.rb.read(self.b).unwrap_or_default();
+            tsrb.notify_writers();
+            return Poll::Ready(Ok(read));
+        }
+        if state.closed {
+            return Poll::Ready(Err(ReadError::Closed));
+        }
+
+        if !self.counted {
+            state.blocked_reads += 1;
+            self.counted = true;
+        }
+        register(&tsrb.inner.read_wakers, cx.waker());
+        Poll::Pending
+    }
+}
+
+// Повторный poll той же задачи не должен раздувать список
+fn register(wakers: &Mutex<Vec<Waker>>, waker: &Waker) {
+    let mut wakers = wakers.lock().unwrap();
+    if !wakers.iter().any(|w| w.will_wake(waker)) {
+        wakers.push(waker.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+    use std::sync::{Arc, Condvar};
+    use std::task::Wake;
+    use std::thread::{scope, sleep};
+    use std::time::Duration;
+
+    // Крошечный однопоточный исполнитель: Waker задачи кладёт её номер в очередь,
+    // а run опрашивает задачи из очереди, пока все не завершатся
+    #[derive(Default)]
+    struct Queue {
+        ready: Mutex<VecDeque<usize>>,
+        condvar: Condvar,
+    }
+
+    struct TaskWaker {
+        id: usize,
+        queue: Arc<Queue>,
+    }
+
+    impl Wake for TaskWaker {
+        fn wake(self: Arc<Self>) {
+            self.queue.ready.lock().unwrap().push_back(self.id);
+            self.queue.condvar.notify_one();
+        }
+    }
+
+    type Task<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+    fn run(tasks: Vec<Task<'_>>) {
+        let queue = Arc::new(Queue::default());
+        queue.ready.lock().unwrap().extend(0..tasks.len());
+        let mut tasks: Vec<Option<Task<'_>>> = tasks.into_iter().map(Some).collect();
+        let mut left = tasks.len();
+
+        while left > 0 {
+            let id = {
+                let mut ready = queue.ready.lock().unwrap();
+                loop {
+                    match ready.pop_front() {
+                        Some(id) => break id,
+                        None => ready = queue.condvar.wait(ready).unwrap(),
+                    }
+                }
+            };
+            let Some(task) = &mut tasks[id] else {
+                continue;
+            };
+
+            let waker = Waker::from(Arc::new(TaskWaker {
+                id,
+                queue: queue.clone(),
+            }));
+            if task
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                tasks[id] = None;
+                left -= 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_producer_consumer_interleave() {
+        let tsrb = ThreadSafeRingBuffer::new(2);
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let producer = {
+            let (tsrb, log) = (&tsrb, log.clone());
+            async move {
+                for item in ["a", "b", "c", "d"] {
+                    assert_eq!(tsrb.write_async(item).await, Ok(1));
+                    log.borrow_mut().push(format!("w{}", item));
+                }
+                tsrb.close();
+            }
+        };
+        let consumer = {
+            let (tsrb, log) = (&tsrb, log.clone());
+            async move {
+                while let Ok(read) = tsrb.read_async(1).await {
+                    log.borrow_mut().push(format!("r{}", read));
+                }
+            }
+        };
+        run(vec![Box::pin(consumer), Box::pin(producer)]);
+
+        // Читатель сначала ждёт данных, писатель заполняет буфер и ждёт места,
+        // дальше задачи сменяют друг друга
+        assert_eq!(
+            *log.borrow(),
+            ["wa", "wb", "ra", "rb", "wc", "wd", "rc", "rd"]
+        );
+        assert_eq!(tsrb.metrics().blocked_reads, 2);
+    }
+
+    #[test]
+    fn test_woken_from_thread() {
+        let tsrb = ThreadSafeRingBuffer::new(4);
+        let read = RefCell::new(String::new());
+
+        scope(|s| {
+            s.spawn(|| {
+                sleep(Duration::from_millis(20));
+                let _ = tsrb.write("дом");
+            });
+
+            run(vec![Box::pin(async {
+                *read.borrow_mut() = tsrb.read_async(4).await.unwrap();
+            })]);
+        });
+        assert_eq!(*read.borrow(), "до");
+    }
+
+    #[test]
+    fn test_errors() {
+        let tsrb = ThreadSafeRingBuffer::new(2);
+        run(vec![Box::pin(async {
+            assert_eq!(tsrb.write_async("🦀").await, Err(WriteError::NoSpaceLeft));
+            assert_eq!(tsrb.write_async("ab").await, Ok(2));
+            tsrb.close();
+            assert_eq!(tsrb.write_async("c").await, Err(WriteError::Closed));
+            assert_eq!(tsrb.read_async(5).await, Ok("ab".to_string()));
+            assert_eq!(tsrb.read_async(5).await, Err(ReadError::Closed));
+        })]);
+    }
+}
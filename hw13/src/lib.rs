@@ -1,107 +1,681 @@
-use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::Waker;
+use std::time::{Duration, Instant};
+
+pub use ring_buffer::{Metrics, MetricsFormat, MetricsReporter, OverflowPolicy};
+
+pub mod channel;
+pub mod future;
+pub mod lockfree;
+pub mod select;
+#[cfg(target_os = "linux")]
+pub mod shm;
 
 #[derive(Debug, PartialEq)]
 pub enum WriteError {
     NoSpaceLeft,
+    Timeout,
+    // Буфер закрыт, писать в него больше нельзя
+    Closed,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReadError {
+    Timeout,
+    // Буфер закрыт и все данные из него прочитаны
+    Closed,
 }
 
+// Текстовый буфер из общего крейта ring_buffer: символы UTF-8
+// записываются и читаются только целиком
 pub struct RingBuffer {
-    read_idx: usize,
-    write_idx: usize,
-    is_full: bool,
-    data: Vec<u8>,
+    inner: ring_buffer::TextRingBuffer,
 }
 
 impl RingBuffer {
     pub fn new(size: usize) -> Self {
+        Self::with_policy(size, OverflowPolicy::default())
+    }
+
+    // В режиме OverwriteOldest запись в полный буфер вытесняет самые старые символы
+    pub fn with_policy(size: usize, policy: OverflowPolicy) -> Self {
         RingBuffer {
-            read_idx: 0,
-            write_idx: 0,
-            is_full: false,
-            data: vec![0; size],
+            inner: ring_buffer::TextRingBuffer::with_policy(size, policy),
+        }
+    }
+
+    pub fn write(&mut self, buf: &str) -> Result<usize, WriteError> {
+        if buf.is_empty() && self.inner.is_full() && self.inner.policy() == OverflowPolicy::Reject {
+            return Err(WriteError::NoSpaceLeft);
         }
-}
 
-pub fn write(&mut self, buf: &str) -> Result<usize, WriteError> {
-    if self.is_full {
-        return Err(WriteError::NoSpaceLeft);
+        // Если не поместился ни один символ, считаем, что места нет
+        let written = self.inner.write(buf);
+        if written.bytes == 0 && !buf.is_empty() {
+            return Err(WriteError::NoSpaceLeft);
+        }
+
+        Ok(written.bytes)
     }
 
-    let buf = buf.as_bytes();
+    // Читает не больше b байт: если в буфере меньше, возвращается только то, что есть.
+    // Символ никогда не разрезается: если первый символ длиннее b байт (и b > 0),
+    // он возвращается целиком, чтобы чтение в цикле всегда продвигалось.
+    pub fn read(&mut self, b: usize) -> Option<String> {
+        // Чтение из пустого буфера тоже проходит через inner, чтобы попасть в метрики
+        let empty = self.inner.is_empty();
+        let read = self.inner.read(b);
+        if empty { None } else { Some(read) }
+    }
+
+    // Как read, но не забирает первый символ, если он длиннее b байт: io::Read
+    // не может отдать больше, чем вмещает переданный ему буфер
+    fn read_fitting(&mut self, b: usize) -> Option<String> {
+        if self.inner.peek_char().is_some_and(|c| c.len_utf8() > b) {
+            return Some(String::new());
+        }
+        self.read(b)
+    }
 
-    let capacity = self.data.len();
+    // Количество байт, записанных в буфер и ещё не прочитанных
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
 
-    let len: usize = if !self.is_full {
-        (self.write_idx + capacity - self.read_idx) % capacity
-    } else {
-        capacity
-    };
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
 
-    let free_space = self.data.len() - len;
-    let bytes_to_write = std::cmp::min(buf.len(), free_space);
+    pub fn is_full(&self) -> bool {
+        self.inner.is_full()
+    }
 
-    for element in buf.iter().take(bytes_to_write) {
-        self.data[self.write_idx] = *element;
-        self.write_idx = (self.write_idx + 1) % capacity;
+    // Сколько байт ещё можно записать
+    pub fn available(&self) -> usize {
+        self.inner.available()
     }
 
-    if self.write_idx == self.read_idx {
-        self.is_full = true
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
     }
 
-    Ok(bytes_to_write)
-}
+    pub fn policy(&self) -> OverflowPolicy {
+        self.inner.policy()
+    }
 
-pub fn read(&mut self, b: usize) -> Option<String> {
-    if self.read_idx == self.write_idx && !self.is_full {
-        return None;
+    // Ёмкость, до которой буфер может вырасти
+    fn max_capacity(&self) -> usize {
+        match self.policy() {
+            OverflowPolicy::Grow { max } => max.max(self.capacity()),
+            _ => self.capacity(),
+        }
     }
-    let mut result = vec![0; b];
-    let capacity = self.data.len();
 
-    for element in result.iter_mut().take(b) {
-        *element = self.data[self.read_idx];
-        self.read_idx = (self.read_idx + 1) % capacity;
+    // Поместится ли запись из bytes байт сразу или после роста буфера. Буфер растёт
+    // не дальше max, поэтому место считается от max, а не от того, что рост ещё возможен.
+    fn fits(&self, bytes: usize) -> bool {
+        match self.policy() {
+            OverflowPolicy::OverwriteOldest => true,
+            _ => self.max_capacity() - self.len() >= bytes,
+        }
     }
 
-    self.is_full = false;
+    // Сколько байт было потеряно из-за перезаписи
+    pub fn dropped(&self) -> u64 {
+        self.inner.dropped()
+    }
 
-    Some(String::from_utf8_lossy(&result).to_string())
+    pub fn metrics(&self) -> Metrics {
+        self.inner.metrics()
     }
 }
 
-#[derive(Clone)]
+// Ждущие писатели стоят в очереди и пишут строго по порядку прихода,
+// поэтому ни один из них не может застрять навсегда.
+struct State {
+    rb: RingBuffer,
+    writers: VecDeque<u64>,
+    next_ticket: u64,
+    blocked_reads: u64,
+    closed: bool,
+    // Сколько живых Writer; когда удаляется последний, буфер закрывается
+    writer_handles: usize,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    nonblocking: AtomicBool,
+    // Сигналы Select, в которых зарегистрирован буфер
+    selects: Mutex<Vec<Arc<select::Signal>>>,
+    // Задачи, ждущие данных или места в ReadFuture и WriteFuture
+    read_wakers: Mutex<Vec<Waker>>,
+    write_wakers: Mutex<Vec<Waker>>,
+}
+
 pub struct ThreadSafeRingBuffer {
-    inner: Arc<Mutex<RingBuffer>>,
+    inner: Arc<Shared>,
+    // Начало символа, пришедшее в io::Write без конца; у каждой ручки своё
+    tail: Utf8Tail,
+}
+
+// Клон - новая ручка того же буфера, незаконченный символ в неё не копируется
+impl Clone for ThreadSafeRingBuffer {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            tail: Utf8Tail::default(),
+        }
+    }
 }
 
 impl ThreadSafeRingBuffer {
     pub fn new(size: usize) -> Self {
+        Self::with_policy(size, OverflowPolicy::default())
+    }
+
+    pub fn with_policy(size: usize, policy: OverflowPolicy) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(RingBuffer::new(size)))
+            inner: Arc::new(Shared {
+                state: Mutex::new(State {
+                    rb: RingBuffer::with_policy(size, policy),
+                    writers: VecDeque::new(),
+                    next_ticket: 0,
+                    blocked_reads: 0,
+                    closed: false,
+                    writer_handles: 0,
+                }),
+                not_empty: Condvar::new(),
+                not_full: Condvar::new(),
+                nonblocking: AtomicBool::new(false),
+                selects: Mutex::new(Vec::new()),
+                read_wakers: Mutex::new(Vec::new()),
+                write_wakers: Mutex::new(Vec::new()),
+            }),
+            tail: Utf8Tail::default(),
         }
     }
-    pub fn write(&self, buf: &str)  -> Result<usize, WriteError> {
-        let mut guard = self.inner.lock().unwrap();
-        guard.write(buf)
+
+    // Не ждёт. Пока в очереди есть ждущие писатели, место отдаётся им.
+    pub fn write(&self, buf: &str) -> Result<usize, WriteError> {
+        let mut state = self.lock();
+        if state.closed {
+            return Err(WriteError::Closed);
+        }
+        if !state.writers.is_empty() && !buf.is_empty() {
+            return Err(WriteError::NoSpaceLeft);
+        }
+
+        let written = state.rb.write(buf)?;
+        self.notify_readers();
+        Ok(written)
     }
 
     pub fn read(&self, b: usize) -> Option<String> {
-        self.inner.lock().unwrap().read(b)
+        self.read_now(|rb| rb.read(b))
+    }
+
+    // Ждёт, пока в буфер не поместится хотя бы первый символ строки, и записывает
+    // столько, сколько помещается. Если первый символ больше всего буфера,
+    // ждать бесполезно - сразу возвращается NoSpaceLeft.
+    pub fn write_blocking(&self, buf: &str) -> Result<usize, WriteError> {
+        self.write_until(buf, None)
+    }
+
+    pub fn write_timeout(&self, buf: &str, timeout: Duration) -> Result<usize, WriteError> {
+        self.write_until(buf, Some(Instant::now() + timeout))
+    }
+
+    // Ждёт, пока в буфере не появятся данные, и читает не больше b байт.
+    // Из закрытого буфера сначала дочитываются остатки, потом возвращается Closed.
+    pub fn read_blocking(&self, b: usize) -> Result<String, ReadError> {
+        self.read_until(b, None)
+    }
+
+    pub fn read_timeout(&self, b: usize, timeout: Duration) -> Result<String, ReadError> {
+        self.read_until(b, Some(Instant::now() + timeout))
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().rb.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().rb.is_empty()
+    }
+
+    pub fn available(&self) -> usize {
+        self.lock().rb.available()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.lock().rb.capacity()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.lock().rb.dropped()
+    }
+
+    // Закрывает буфер для всех клонов: новые записи возвращают Closed, ждущие
+    // писатели и читатели просыпаются. Повторный вызов ничего не делает.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.notify_readers();
+        self.notify_writers();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    // Ручка только для записи. Буфер закрывается сам, когда удаляется последняя
+    // из ручек, созданных через writer() и их клоны.
+    pub fn writer(&self) -> Writer {
+        self.lock().writer_handles += 1;
+        Writer { tsrb: self.clone() }
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        let state = self.lock();
+        Metrics {
+            blocked_reads: state.blocked_reads,
+            ..state.rb.metrics()
+        }
+    }
+
+    // Раз в interval пишет снимок метрик в sink, пока жив возвращённый MetricsReporter
+    pub fn report_metrics<W: io::Write + Send + 'static>(
+        &self,
+        interval: Duration,
+        format: MetricsFormat,
+        sink: W,
+    ) -> MetricsReporter {
+        let tsrb = self.clone();
+        MetricsReporter::spawn(interval, format, move || tsrb.metrics(), sink)
+    }
+
+    // Режим для io::Read и io::Write, общий для всех клонов. По умолчанию они ждут
+    // места или данных; в неблокирующем режиме сразу возвращают ErrorKind::WouldBlock.
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.inner.nonblocking.store(nonblocking, Ordering::Relaxed);
+    }
+
+    fn is_nonblocking(&self) -> bool {
+        self.inner.nonblocking.load(Ordering::Relaxed)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.inner.state.lock().unwrap()
+    }
+
+    // Будит ждущих читателей, их задачи и все Select, в которых зарегистрирован буфер
+    fn notify_readers(&self) {
+        self.inner.not_empty.notify_all();
+        wake_all(&self.inner.read_wakers);
+        self.notify_selects();
+    }
+
+    fn notify_writers(&self) {
+        self.inner.not_full.notify_all();
+        wake_all(&self.inner.write_wakers);
+        self.notify_selects();
+    }
+
+    fn notify_selects(&self) {
+        for signal in self.inner.selects.lock().unwrap().iter() {
+            signal.notify();
+        }
+    }
+
+    fn write_until(&self, buf: &str, deadline: Option<Instant>) -> Result<usize, WriteError> {
+        let first = buf.chars().next().map_or(0, char::len_utf8);
+        self.write_when_fits(buf, first, deadline)
+    }
+
+    // Ждёт своей очереди и места под needed байт, потом пишет buf
+    fn write_when_fits(
+        &self,
+        buf: &str,
+        needed: usize,
+        deadline: Option<Instant>,
+    ) -> Result<usize, WriteError> {
+        let mut state = self.lock();
+        if state.closed {
+            return Err(WriteError::Closed);
+        }
+        if needed > state.rb.max_capacity() {
+            return Err(WriteError::NoSpaceLeft);
+        }
+
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.writers.push_back(ticket);
+
+        loop {
+            if state.closed {
+                state.writers.retain(|&t| t != ticket);
+                return Err(WriteError::Closed);
+            }
+            if state.writers.front() == Some(&ticket) && state.rb.fits(needed) {
+                break;
+            }
+
+            let timed_out;
+            (state, timed_out) = wait(&self.inner.not_full, state, deadline);
+            if timed_out {
+                state.writers.retain(|&t| t != ticket);
+                // Очередь сдвинулась: следующий писатель может оказаться первым
+                self.notify_writers();
+                return Err(WriteError::Timeout);
+            }
+        }
+
+        state.writers.pop_front();
+        let written = state.rb.write(buf);
+        self.notify_readers();
+        self.notify_writers();
+        written
+    }
+
+    fn read_until(&self, b: usize, deadline: Option<Instant>) -> Result<String, ReadError> {
+        self.read_with(deadline, |rb| rb.read(b).unwrap_or_default())
+    }
+
+    // Забирает данные через take, не дожидаясь их
+    fn read_now(&self, take: impl FnOnce(&mut RingBuffer) -> Option<String>) -> Option<String> {
+        let read = take(&mut self.lock().rb);
+        if read.is_some() {
+            self.notify_writers();
+        }
+        read
+    }
+
+    // Ждёт данных и забирает их через take
+    fn read_with<T>(
+        &self,
+        deadline: Option<Instant>,
+        take: impl FnOnce(&mut RingBuffer) -> T,
+    ) -> Result<T, ReadError> {
+        let mut state = self.lock();
+        if state.rb.is_empty() && !state.closed {
+            state.blocked_reads += 1;
+        }
+
+        loop {
+            if !state.rb.is_empty() {
+                let read = take(&mut state.rb);
+                self.notify_writers();
+                return Ok(read);
+            }
+            if state.closed {
+                return Err(ReadError::Closed);
+            }
+
+            let timed_out;
+            (state, timed_out) = wait(&self.inner.not_empty, state, deadline);
+            if timed_out {
+                return Err(ReadError::Timeout);
+            }
+        }
+    }
+}
+
+// Буфер текстовый, поэтому в него попадают только целые символы UTF-8. Незаконченный
+// символ в конце buf (не больше 3 байт) считается принятым и хранится в ручке, пока
+// следующие вызовы write не принесут его конец. Ошибка InvalidData - только для байт,
+// которые не могут быть частью UTF-8. flush недописанный символ не записывает.
+impl io::Write for ThreadSafeRingBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.tail.is_empty() {
+            return self.complete_tail(buf);
+        }
+
+        let (text, rest) = match std::str::from_utf8(buf) {
+            Ok(text) => (text, &[][..]),
+            Err(e) => {
+                let (valid, rest) = buf.split_at(e.valid_up_to());
+                // Неверные байты сразу за верным началом вернут ошибку следующим вызовом
+                if e.error_len().is_some() && valid.is_empty() {
+                    return Err(invalid_utf8());
+                }
+                let rest = if e.error_len().is_some() {
+                    &[][..]
+                } else {
+                    rest
+                };
+                (std::str::from_utf8(valid).unwrap(), rest)
+            }
+        };
+
+        let written = if text.is_empty() {
+            0
+        } else {
+            self.write_text(text)?
+        };
+        if written == text.len() && !rest.is_empty() {
+            self.tail.extend(rest);
+            return Ok(written + rest.len());
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ThreadSafeRingBuffer {
+    // Записывает text так же, как io::Write: ждёт места или возвращает WouldBlock
+    fn write_text(&self, text: &str) -> io::Result<usize> {
+        let nonblocking = self.is_nonblocking();
+        let result = if nonblocking {
+            ThreadSafeRingBuffer::write(self, text)
+        } else {
+            self.write_blocking(text)
+        };
+
+        match result {
+            Ok(written) => Ok(written),
+            Err(WriteError::Closed) => Err(io::ErrorKind::BrokenPipe.into()),
+            Err(WriteError::NoSpaceLeft) if nonblocking => Err(io::ErrorKind::WouldBlock.into()),
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "символ больше буфера",
+            )),
+        }
+    }
+
+    // Дописывает отложенный символ байтами из начала buf. Если записать символ
+    // не удалось, байты из buf не считаются принятыми.
+    fn complete_tail(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let taken = std::cmp::min(self.tail.missing(), buf.len());
+        let before = self.tail.len;
+        self.tail.extend(&buf[..taken]);
+        if self.tail.missing() > 0 {
+            return Ok(taken);
+        }
+
+        let Ok(text) = std::str::from_utf8(self.tail.as_bytes()) else {
+            self.tail = Utf8Tail::default();
+            return Err(invalid_utf8());
+        };
+        match self.write_text(text) {
+            Ok(_) => {
+                self.tail = Utf8Tail::default();
+                Ok(taken)
+            }
+            Err(e) => {
+                self.tail.len = before;
+                Err(e)
+            }
+        }
+    }
+}
+
+// Начало многобайтового символа UTF-8
+#[derive(Default)]
+struct Utf8Tail {
+    bytes: [u8; 4],
+    len: usize,
+}
+
+impl Utf8Tail {
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    fn extend(&mut self, bytes: &[u8]) {
+        self.bytes[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    // Сколько байт не хватает до конца символа; длина задаётся первым байтом
+    fn missing(&self) -> usize {
+        let width = match self.bytes[0] {
+            0xf0.. => 4,
+            0xe0.. => 3,
+            _ => 2,
+        };
+        width - self.len
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "ожидался UTF-8")
+}
+
+// Символы не разрезаются: если out меньше первого символа, возвращается InvalidInput.
+// Закрытый и прочитанный до конца буфер отдаёт Ok(0), как конец файла.
+impl io::Read for ThreadSafeRingBuffer {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+
+        let text = if self.is_nonblocking() {
+            match self.read_now(|rb| rb.read_fitting(out.len())) {
+                Some(text) => text,
+                None if self.is_closed() => return Ok(0),
+                None => return Err(io::ErrorKind::WouldBlock.into()),
+            }
+        } else {
+            let take = |rb: &mut RingBuffer| rb.read_fitting(out.len()).unwrap_or_default();
+            match self.read_with(None, take) {
+                Ok(text) => text,
+                Err(_) => return Ok(0),
+            }
+        };
+        if text.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "буфер чтения меньше символа",
+            ));
+        }
+
+        out[..text.len()].copy_from_slice(text.as_bytes());
+        Ok(text.len())
+    }
+}
+
+// Ручка писателя над ThreadSafeRingBuffer. Читать через неё нельзя, а удаление
+// последней ручки закрывает буфер, так что читатели узнают о конце данных.
+pub struct Writer {
+    tsrb: ThreadSafeRingBuffer,
+}
+
+impl Writer {
+    pub fn write(&self, buf: &str) -> Result<usize, WriteError> {
+        self.tsrb.write(buf)
+    }
+
+    pub fn write_blocking(&self, buf: &str) -> Result<usize, WriteError> {
+        self.tsrb.write_blocking(buf)
+    }
+
+    pub fn write_timeout(&self, buf: &str, timeout: Duration) -> Result<usize, WriteError> {
+        self.tsrb.write_timeout(buf, timeout)
+    }
+
+    pub fn close(&self) {
+        self.tsrb.close();
+    }
+}
+
+impl Clone for Writer {
+    fn clone(&self) -> Self {
+        self.tsrb.writer()
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let mut state = self.tsrb.lock();
+        state.writer_handles -= 1;
+        if state.writer_handles == 0 {
+            drop(state);
+            self.tsrb.close();
+        }
+    }
+}
+
+impl io::Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::Write::write(&mut self.tsrb, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Будит задачи уже после того, как список отпущен: задача может сразу же
+// зарегистрироваться заново
+fn wake_all(wakers: &Mutex<Vec<Waker>>) {
+    let wakers = std::mem::take(&mut *wakers.lock().unwrap());
+    for waker in wakers {
+        waker.wake();
+    }
+}
+
+// Ждёт сигнала до крайнего срока; возвращает true, если срок истёк
+fn wait<'a>(
+    condvar: &Condvar,
+    guard: MutexGuard<'a, State>,
+    deadline: Option<Instant>,
+) -> (MutexGuard<'a, State>, bool) {
+    match deadline {
+        None => (condvar.wait(guard).unwrap(), false),
+        Some(deadline) => {
+            let now = Instant::now();
+            if now >= deadline {
+                return (guard, true);
+            }
+            let (guard, _) = condvar.wait_timeout(guard, deadline - now).unwrap();
+            (guard, false)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
-    use std::thread::scope;
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::{HashMap, VecDeque};
+    use std::thread::{scope, sleep};
 
     #[test]
     fn test_create_buffer() {
         let rb = RingBuffer::new(3);
-        assert_eq!(rb.data.capacity(), 3);
-        assert!(!rb.is_full);
+        assert_eq!(rb.capacity(), 3);
+        assert!(!rb.is_full());
     }
 
     #[test]
@@ -117,7 +691,7 @@ mod tests {
         let mut rb: RingBuffer = RingBuffer::new(3);
         let b = rb.write("abc");
         assert_eq!(b, Ok(3));
-        assert!(rb.is_full);
+        assert!(rb.is_full());
     }
 
     #[test]
@@ -126,7 +700,7 @@ mod tests {
         let _b = rb.write("ab");
         let d = rb.read(1);
         assert_eq!(d, Some("a".to_string()));
-        assert_eq!(rb.read_idx, 1);
+        assert_eq!(rb.inner.read_idx(), 1);
     }
 
     #[test]
@@ -136,9 +710,9 @@ mod tests {
         let d = rb.read(1);
 
         assert_eq!(d, Some("a".to_string()));
-        assert_eq!(rb.read_idx, 1);
-        assert_eq!(rb.write_idx, 0);
-        assert!(!rb.is_full);
+        assert_eq!(rb.inner.read_idx(), 1);
+        assert_eq!(rb.inner.write_idx(), 0);
+        assert!(!rb.is_full());
     }
 
     #[test]
@@ -147,27 +721,27 @@ mod tests {
 
         let mut b = rb.write("ab");
         assert_eq!(b, Ok(2));
-        assert_eq!(rb.write_idx, 2);
+        assert_eq!(rb.inner.write_idx(), 2);
 
         b = rb.write("cd");
         assert_eq!(b, Ok(1));
-        assert_eq!(rb.write_idx, 0);
-        assert!(rb.is_full);
+        assert_eq!(rb.inner.write_idx(), 0);
+        assert!(rb.is_full());
 
         let d = rb.read(1);
         assert_eq!(d, Some("a".to_string()));
-        assert_eq!(rb.read_idx, 1);
-        assert!(!rb.is_full);
+        assert_eq!(rb.inner.read_idx(), 1);
+        assert!(!rb.is_full());
 
         b = rb.write("e");
         assert_eq!(b, Ok(1));
-        assert_eq!(rb.write_idx, 1);
-        assert!(rb.is_full);
+        assert_eq!(rb.inner.write_idx(), 1);
+        assert!(rb.is_full());
 
         let d = rb.read(2);
         assert_eq!(d, Some("bc".to_string()));
-        assert_eq!(rb.read_idx, 0);
-        assert!(!rb.is_full);
+        assert_eq!(rb.inner.read_idx(), 0);
+        assert!(!rb.is_full());
     }
 
     #[test]
@@ -187,6 +761,158 @@ mod tests {
         assert_eq!(b, Err(WriteError::NoSpaceLeft))
     }
 
+    #[test]
+    fn test_read_more_than_stored() {
+        let mut rb = RingBuffer::new(3);
+        let _ = rb.write("ab");
+
+        let d = rb.read(3);
+        assert_eq!(d, Some("ab".to_string()));
+        assert_eq!(rb.inner.read_idx(), 2);
+        assert_eq!(rb.inner.write_idx(), 2);
+        assert!(rb.is_empty());
+
+        assert_eq!(rb.read(1), None);
+        assert_eq!(rb.inner.read_idx(), 2);
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut rb = RingBuffer::new(3);
+        assert_eq!(rb.capacity(), 3);
+        assert_eq!(rb.len(), 0);
+        assert_eq!(rb.available(), 3);
+        assert!(rb.is_empty());
+
+        let _ = rb.write("abc");
+        assert_eq!(rb.len(), 3);
+        assert_eq!(rb.available(), 0);
+
+        rb.read(2);
+        let _ = rb.write("d");
+        assert_eq!(rb.len(), 2);
+        assert_eq!(rb.available(), 1);
+        assert!(!rb.is_empty());
+    }
+
+    #[test]
+    fn test_write_empty_str() {
+        let mut rb = RingBuffer::new(3);
+        assert_eq!(rb.write(""), Ok(0));
+        assert!(!rb.is_full());
+        assert_eq!(rb.read(1), None);
+    }
+
+    #[test]
+    fn test_cyrillic() {
+        let mut rb = RingBuffer::new(5);
+        assert_eq!(rb.write("ёжик"), Ok(4));
+        assert_eq!(rb.write("ы"), Err(WriteError::NoSpaceLeft));
+        assert_eq!(rb.read(3), Some("ё".to_string()));
+        assert_eq!(rb.read(1), Some("ж".to_string()));
+        assert_eq!(rb.read(2), None);
+    }
+
+    #[test]
+    fn test_byte_read_loop_ends() {
+        let mut rb = RingBuffer::new(8);
+        rb.write("я🦀a").unwrap();
+        let mut text = String::new();
+        while let Some(chunk) = rb.read(1) {
+            text.push_str(&chunk);
+        }
+        assert_eq!(text, "я🦀a");
+    }
+
+    #[test]
+    fn test_emoji_thread_safe() {
+        let tsrb = ThreadSafeRingBuffer::new(6);
+        assert_eq!(tsrb.write("🦀🦀"), Ok(4));
+        assert_eq!(tsrb.write("a🦀"), Ok(1));
+        assert_eq!(tsrb.read(6), Some("🦀a".to_string()));
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut rb = RingBuffer::with_policy(3, OverflowPolicy::OverwriteOldest);
+        assert_eq!(rb.write("abc"), Ok(3));
+        assert_eq!(rb.write("de"), Ok(2));
+        assert_eq!(rb.dropped(), 2);
+        assert_eq!(rb.inner.read_idx(), 2);
+        assert_eq!(rb.read(3), Some("cde".to_string()));
+    }
+
+    #[test]
+    fn test_overwrite_oldest_thread_safe() {
+        let tsrb = ThreadSafeRingBuffer::with_policy(4, OverflowPolicy::OverwriteOldest);
+        for line in ["one", "two", "three"] {
+            assert_eq!(tsrb.write(line), Ok(line.len()));
+        }
+        assert_eq!(tsrb.dropped(), 7);
+        assert_eq!(tsrb.read(10), Some("hree".to_string()));
+    }
+
+    #[test]
+    fn test_accessors_thread_safe() {
+        let tsrb = ThreadSafeRingBuffer::new(3);
+        let _ = tsrb.write("ab");
+        assert_eq!(tsrb.len(), 2);
+        assert_eq!(tsrb.available(), 1);
+        assert_eq!(tsrb.capacity(), 3);
+        assert!(!tsrb.is_empty());
+        assert_eq!(tsrb.read(5), Some("ab".to_string()));
+        assert!(tsrb.is_empty());
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Write(String),
+        Read(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            "[a-z]{0,8}".prop_map(Op::Write),
+            (0..8usize).prop_map(Op::Read),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_vec_deque(size in 1..8usize, ops in prop::collection::vec(op(), 0..64)) {
+            let mut rb = RingBuffer::new(size);
+            let mut model = VecDeque::new();
+
+            for op in ops {
+                match op {
+                    Op::Write(buf) => {
+                        let result = rb.write(&buf);
+                        if model.len() == size {
+                            prop_assert_eq!(result, Err(WriteError::NoSpaceLeft));
+                        } else {
+                            let expected = buf.len().min(size - model.len());
+                            model.extend(&buf.as_bytes()[..expected]);
+                            prop_assert_eq!(result, Ok(expected));
+                        }
+                    }
+                    Op::Read(b) => {
+                        let expected = if model.is_empty() {
+                            None
+                        } else {
+                            let bytes: Vec<u8> = model.drain(..b.min(model.len())).collect();
+                            Some(String::from_utf8(bytes).unwrap())
+                        };
+                        prop_assert_eq!(rb.read(b), expected);
+                    }
+                }
+                prop_assert_eq!(rb.len(), model.len());
+                prop_assert_eq!(rb.is_empty(), model.is_empty());
+                prop_assert_eq!(rb.available(), size - model.len());
+                prop_assert_eq!(rb.capacity(), size);
+            }
+        }
+    }
+
     #[test]
     fn test_simple_write_thread_safe() {
         let tsrb = ThreadSafeRingBuffer::new(3);
@@ -203,6 +929,323 @@ mod tests {
         assert_eq!(d, Some("a".to_string()));
     }
 
+    #[test]
+    fn test_grow_thread_safe() {
+        let tsrb = ThreadSafeRingBuffer::with_policy(1, OverflowPolicy::Grow { max: 4 });
+        // Буфер вырастет под эмодзи, поэтому писатель не ждёт
+        assert_eq!(tsrb.write_blocking("🦀"), Ok(4));
+        assert_eq!(tsrb.capacity(), 4);
+        assert_eq!(
+            tsrb.write_timeout("a", Duration::from_millis(10)),
+            Err(WriteError::Timeout)
+        );
+    }
+
+    #[test]
+    fn test_grow_near_max() {
+        let tsrb = ThreadSafeRingBuffer::with_policy(2, OverflowPolicy::Grow { max: 5 });
+        assert_eq!(tsrb.write("abcd"), Ok(4));
+        // Буфер ещё может вырасти, но только на 1 байт: двухбайтовому символу надо ждать
+        assert_eq!(
+            tsrb.write_timeout("ж", Duration::from_millis(10)),
+            Err(WriteError::Timeout)
+        );
+
+        scope(|s| {
+            let writer = s.spawn(|| tsrb.write_blocking("жы"));
+            while tsrb.lock().writers.is_empty() {
+                sleep(Duration::from_millis(1));
+            }
+            assert_eq!(tsrb.read(1), Some("a".to_string()));
+            assert_eq!(writer.join().unwrap(), Ok(2));
+        });
+        assert_eq!(tsrb.read(10), Some("bcdж".to_string()));
+        assert!(tsrb.capacity() <= 5);
+    }
+
+    #[test]
+    fn test_metrics_thread_safe() {
+        let tsrb = ThreadSafeRingBuffer::new(4);
+        assert_eq!(tsrb.read(1), None);
+        assert_eq!(tsrb.write("abcdef"), Ok(4));
+        assert_eq!(tsrb.write("g"), Err(WriteError::NoSpaceLeft));
+        assert_eq!(tsrb.read(10), Some("abcd".to_string()));
+
+        scope(|s| {
+            let reader = s.spawn(|| tsrb.read_blocking(1).unwrap());
+            while tsrb.metrics().blocked_reads == 0 {
+                sleep(Duration::from_millis(1));
+            }
+            let _ = tsrb.write("h");
+            assert_eq!(reader.join().unwrap(), "h");
+        });
+
+        assert_eq!(
+            tsrb.metrics(),
+            Metrics {
+                high_water_mark: 4,
+                items_in: 5,
+                items_out: 5,
+                rejected_writes: 2,
+                empty_reads: 1,
+                blocked_reads: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_report_metrics() {
+        let tsrb = ThreadSafeRingBuffer::new(4);
+        let _ = tsrb.write("ab");
+        let (sink, lines) = std::sync::mpsc::channel();
+
+        struct Lines(std::sync::mpsc::Sender<String>);
+        impl io::Write for Lines {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let _ = self.0.send(String::from_utf8_lossy(buf).into_owned());
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let reporter =
+            tsrb.report_metrics(Duration::from_millis(5), MetricsFormat::Text, Lines(sink));
+        let line = lines.recv().unwrap();
+        drop(reporter);
+        assert!(line.starts_with("high_water_mark=2 items_in=2 items_out=0"));
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let tsrb = ThreadSafeRingBuffer::new(2);
+        let timeout = Duration::from_millis(20);
+        assert_eq!(tsrb.read_timeout(1, timeout), Err(ReadError::Timeout));
+
+        assert_eq!(tsrb.write_timeout("abc", timeout), Ok(2));
+        assert_eq!(tsrb.write_timeout("c", timeout), Err(WriteError::Timeout));
+        assert_eq!(tsrb.read_timeout(5, timeout), Ok("ab".to_string()));
+        // Символ больше буфера не поместится никогда
+        assert_eq!(tsrb.write_blocking("🦀"), Err(WriteError::NoSpaceLeft));
+    }
+
+    #[test]
+    fn test_blocking_wakes_up() {
+        let tsrb = ThreadSafeRingBuffer::new(2);
+        let _ = tsrb.write("ab");
+
+        scope(|s| {
+            let writer = s.spawn(|| tsrb.write_blocking("cd"));
+            sleep(Duration::from_millis(20));
+            assert_eq!(tsrb.read(1), Some("a".to_string()));
+            assert_eq!(writer.join().unwrap(), Ok(1));
+
+            let reader = s.spawn(|| tsrb.read_blocking(5).unwrap());
+            assert_eq!(reader.join().unwrap(), "bc");
+            let reader = s.spawn(|| tsrb.read_blocking(5).unwrap());
+            sleep(Duration::from_millis(20));
+            let _ = tsrb.write("e");
+            assert_eq!(reader.join().unwrap(), "e");
+        });
+    }
+
+    #[test]
+    fn test_writers_are_served_in_order() {
+        let tsrb = &ThreadSafeRingBuffer::new(1);
+        let _ = tsrb.write("x");
+        let waiting = || tsrb.lock().writers.len();
+
+        scope(|s| {
+            for item in ["a", "b", "c"] {
+                let queued = waiting();
+                s.spawn(move || tsrb.write_blocking(item));
+                while waiting() == queued {
+                    sleep(Duration::from_millis(1));
+                }
+            }
+
+            // Неждущий писатель не может влезть вперёд очереди
+            assert_eq!(tsrb.read(1), Some("x".to_string()));
+            assert_eq!(tsrb.write("z"), Err(WriteError::NoSpaceLeft));
+
+            let read: Vec<String> = (0..3).map(|_| tsrb.read_blocking(1).unwrap()).collect();
+            assert_eq!(read, ["a", "b", "c"]);
+        });
+    }
+
+    #[test]
+    fn test_io_nonblocking() {
+        use std::io::{Read, Write};
+
+        let mut tsrb = ThreadSafeRingBuffer::new(3);
+        tsrb.set_nonblocking(true);
+        write!(tsrb, "ё{}", 1).unwrap();
+        let err = Write::write(&mut tsrb, b"ab").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        let mut out = [0; 1];
+        let err = Read::read(&mut tsrb, &mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let mut out = [0; 8];
+        assert_eq!(Read::read(&mut tsrb, &mut out).unwrap(), 3);
+        assert_eq!(&out[..3], "ё1".as_bytes());
+        let err = Read::read(&mut tsrb, &mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn test_io_utf8() {
+        use std::io::Write;
+
+        let mut tsrb = ThreadSafeRingBuffer::new(8);
+        // От "ж" пришёл только первый байт: он принят и ждёт второго
+        let bytes = "aж".as_bytes();
+        assert_eq!(Write::write(&mut tsrb, &bytes[..2]).unwrap(), 2);
+        assert_eq!(tsrb.read(8), Some("a".to_string()));
+        assert_eq!(Write::write(&mut tsrb, &bytes[2..]).unwrap(), 1);
+        assert_eq!(tsrb.read(8), Some("ж".to_string()));
+
+        let err = Write::write(&mut tsrb, &[0xff]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // Верное начало записывается, ошибка - на следующем вызове
+        assert_eq!(Write::write(&mut tsrb, b"b\xffc").unwrap(), 1);
+        assert!(Write::write(&mut tsrb, b"\xffc").is_err());
+        // Продолжение, которое не подходит к началу символа, - тоже ошибка
+        assert_eq!(Write::write(&mut tsrb, &bytes[1..2]).unwrap(), 1);
+        let err = Write::write(&mut tsrb, b"c").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(tsrb.read(8), Some("b".to_string()));
+    }
+
+    #[test]
+    fn test_io_copy_chunked() {
+        use std::io::{Read, Write};
+
+        // Источник отдаёт по 5 байт, так что символы режутся на границах кусков
+        struct Chunks<'a>(&'a [u8]);
+
+        impl Read for Chunks<'_> {
+            fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+                let n = self.0.len().min(out.len()).min(5);
+                out[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let text = "Привет, 🦀! Кольцевой буфер 🦀🦀 и ёжик";
+        let tsrb = ThreadSafeRingBuffer::new(6);
+        let received = scope(|s| {
+            let mut writer = tsrb.writer();
+            s.spawn(move || {
+                let copied = io::copy(&mut Chunks(text.as_bytes()), &mut writer).unwrap();
+                assert_eq!(copied, text.len() as u64);
+                writer.flush().unwrap();
+            });
+
+            let mut received = String::new();
+            while let Ok(read) = tsrb.read_blocking(16) {
+                received.push_str(&read);
+            }
+            received
+        });
+        assert_eq!(received, text);
+    }
+
+    #[test]
+    fn test_io_blocking_lines() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let tsrb = ThreadSafeRingBuffer::new(8);
+        let lines = scope(|s| {
+            let mut writer = tsrb.clone();
+            s.spawn(move || {
+                for i in 0..100 {
+                    writeln!(writer, "строка {}", i).unwrap();
+                }
+            });
+
+            BufReader::new(tsrb.clone())
+                .lines()
+                .take(100)
+                .collect::<io::Result<Vec<_>>>()
+                .unwrap()
+        });
+
+        assert_eq!(lines[0], "строка 0");
+        assert_eq!(lines[99], "строка 99");
+    }
+
+    #[test]
+    fn test_close() {
+        let tsrb = ThreadSafeRingBuffer::new(4);
+        assert_eq!(tsrb.write("abc"), Ok(3));
+        tsrb.close();
+        assert!(tsrb.is_closed());
+        assert_eq!(tsrb.write("d"), Err(WriteError::Closed));
+        assert_eq!(tsrb.write_blocking("d"), Err(WriteError::Closed));
+
+        // Остатки дочитываются, после них - конец данных
+        assert_eq!(tsrb.read_blocking(2), Ok("ab".to_string()));
+        assert_eq!(tsrb.read_blocking(2), Ok("c".to_string()));
+        assert_eq!(tsrb.read_blocking(2), Err(ReadError::Closed));
+        assert_eq!(
+            tsrb.read_timeout(2, Duration::from_millis(10)),
+            Err(ReadError::Closed)
+        );
+        assert_eq!(tsrb.read(2), None);
+    }
+
+    #[test]
+    fn test_close_wakes_up_waiting() {
+        let tsrb = &ThreadSafeRingBuffer::new(1);
+        let _ = tsrb.write("x");
+
+        scope(|s| {
+            let writer = s.spawn(|| tsrb.write_blocking("y"));
+            while tsrb.lock().writers.is_empty() {
+                sleep(Duration::from_millis(1));
+            }
+            tsrb.close();
+            assert_eq!(writer.join().unwrap(), Err(WriteError::Closed));
+        });
+
+        assert_eq!(tsrb.read_blocking(1), Ok("x".to_string()));
+        scope(|s| {
+            let reader = s.spawn(|| tsrb.read_blocking(1));
+            assert_eq!(reader.join().unwrap(), Err(ReadError::Closed));
+        });
+    }
+
+    #[test]
+    fn test_last_writer_closes() {
+        use std::io::Read;
+
+        let tsrb = ThreadSafeRingBuffer::new(8);
+        let writer = tsrb.writer();
+        let text = scope(|s| {
+            for i in 0..4 {
+                let writer = writer.clone();
+                s.spawn(move || {
+                    writer.write_blocking(&i.to_string()).unwrap();
+                });
+            }
+            drop(writer);
+
+            // Читатель не знает, сколько придёт данных: он читает до конца
+            let mut text = String::new();
+            tsrb.clone().read_to_string(&mut text).unwrap();
+            text
+        });
+
+        let mut chars: Vec<char> = text.chars().collect();
+        chars.sort();
+        assert_eq!(chars, ['0', '1', '2', '3']);
+        assert!(tsrb.is_closed());
+    }
+
     #[test]
     fn test_multithread_write_read() {
         let tsrb = ThreadSafeRingBuffer::new(100);
@@ -215,11 +1258,7 @@ mod tests {
                 s.spawn(move || {
                     for _ in 0..items_per_thread {
                         let item = i.to_string();
-                        loop {
-                            if writer_buffer.write(&item).is_ok() {
-                                break;
-                            }
-                        }
+                        assert_eq!(writer_buffer.write_blocking(&item), Ok(1));
                     }
                 });
             }
@@ -230,12 +1269,7 @@ mod tests {
                 let handle = s.spawn(move || {
                     let mut my_reads = Vec::with_capacity(items_per_thread);
                     for _ in 0..items_per_thread {
-                        loop {
-                            if let Some(value) = reader_buffer.read(1) {
-                                my_reads.push(value);
-                                break;
-                            }
-                        }
+                        my_reads.push(reader_buffer.read_blocking(1).unwrap());
                     }
                     my_reads
                 });
@@ -262,8 +1296,11 @@ mod tests {
         for i in 0..num_threads {
             let expected_str = i.to_string();
             let count = counts.get(&expected_str).unwrap_or(&0);
-            assert_eq!(*count, items_per_thread,
-                       "Ошибка подсчета для строки '{}'", expected_str);
+            assert_eq!(
+                *count, items_per_thread,
+                "Ошибка подсчета для строки '{}'",
+                expected_str
+            );
         }
     }
 }
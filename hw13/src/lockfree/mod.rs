@@ -0,0 +1,214 @@
+// Текстовый буфер без блокировок поверх mpmc::Queue из крейта ring_buffer, с теми же
+// неждущими write/read, что у ThreadSafeRingBuffer.
+//
+// Очередь хранит символы (char), а не байты: писатели кладут символы по одному,
+// и байты одного символа никогда не перемешиваются с байтами другого писателя.
+// Ёмкость, len и аргумент read считаются в байтах, как у ThreadSafeRingBuffer.
+// Для этого байты учитываются отдельным счётчиком: писатель сначала резервирует
+// место под символ, потом кладёт его в очередь, а читатель освобождает место
+// только после того, как забрал символ. Очередь создаётся не меньше ёмкости
+// в байтах, так что символов в ней никогда не больше, чем ячеек.
+//
+// Ждущих write_blocking/read_blocking нет: ждать без блокировки можно только
+// крутясь в цикле, а это удобнее решать снаружи.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::yield_now;
+
+use ring_buffer::mpmc::Queue;
+
+use crate::WriteError;
+
+// Наибольшая длина символа в UTF-8
+const MAX_CHAR_LEN: usize = 4;
+
+struct Shared {
+    queue: Queue<char>,
+    // Занятые байты, включая зарезервированные, но ещё не положенные в очередь символы
+    used: AtomicUsize,
+    capacity: usize,
+}
+
+#[derive(Clone)]
+pub struct LockFreeRingBuffer {
+    inner: Arc<Shared>,
+}
+
+impl LockFreeRingBuffer {
+    // Ёмкость в байтах
+    pub fn new(size: usize) -> Self {
+        Self {
+            inner: Arc::new(Shared {
+                queue: Queue::new(size),
+                used: AtomicUsize::new(0),
+                capacity: size,
+            }),
+        }
+    }
+
+    // Записывает столько символов, сколько помещается, и возвращает число записанных байт.
+    // Символы разных писателей могут перемежаться.
+    pub fn write(&self, buf: &str) -> Result<usize, WriteError> {
+        let written: usize = buf
+            .chars()
+            .take_while(|&c| self.reserve(c.len_utf8()))
+            .map(|c| {
+                // Место зарезервировано, но ячейку круга назад ещё может держать
+                // читатель, который забрал позицию и пока не вернул её
+                let mut c = c;
+                while let Err(rejected) = self.inner.queue.push(c) {
+                    c = rejected;
+                    yield_now();
+                }
+                c.len_utf8()
+            })
+            .sum();
+
+        // Если не поместился ни один символ, считаем, что места нет
+        if written == 0 && !buf.is_empty() {
+            return Err(WriteError::NoSpaceLeft);
+        }
+        Ok(written)
+    }
+
+    // Читает не больше b байт; из пустого буфера возвращает None. Как и у
+    // ThreadSafeRingBuffer, первый символ отдаётся целиком, даже если он длиннее b.
+    // Заглянуть в очередь, не забрав символ, нельзя, поэтому следующий символ
+    // забирается, только если он наверняка поместится: результат может оказаться
+    // на несколько байт короче b, даже когда в буфере есть ещё данные.
+    pub fn read(&self, b: usize) -> Option<String> {
+        let mut read = String::new();
+        if b == 0 {
+            return Some(read);
+        }
+
+        while read.is_empty() || read.len() + MAX_CHAR_LEN <= b {
+            let Some(c) = self.inner.queue.pop() else {
+                break;
+            };
+            self.inner.used.fetch_sub(c.len_utf8(), Ordering::Release);
+            read.push(c);
+        }
+
+        if read.is_empty() { None } else { Some(read) }
+    }
+
+    // Приблизительное количество байт в буфере
+    pub fn len(&self) -> usize {
+        self.inner.used.load(Ordering::Acquire)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+
+    fn reserve(&self, bytes: usize) -> bool {
+        let capacity = self.inner.capacity;
+        self.inner
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                (used + bytes <= capacity).then_some(used + bytes)
+            })
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::thread::scope;
+
+    #[test]
+    fn test_write_read() {
+        let rb = LockFreeRingBuffer::new(9);
+        assert_eq!(rb.capacity(), 9);
+        assert_eq!(rb.write("дом"), Ok(6));
+        assert_eq!(rb.write("ab🦀"), Ok(2));
+        assert_eq!(rb.write("c"), Ok(1));
+        assert_eq!(rb.write("d"), Err(WriteError::NoSpaceLeft));
+        assert_eq!(rb.write(""), Ok(0));
+        assert_eq!(rb.len(), 9);
+
+        // Первый символ отдаётся целиком, даже если он длиннее b
+        assert_eq!(rb.read(1), Some("д".to_string()));
+        assert_eq!(rb.read(0), Some(String::new()));
+        assert_eq!(rb.read(6), Some("ом".to_string()));
+        assert_eq!(rb.len(), 3);
+        assert_eq!(rb.write("🦀🦀"), Ok(4));
+        assert_eq!(rb.read(10), Some("abc🦀".to_string()));
+        assert_eq!(rb.read(1), None);
+        assert!(rb.is_empty());
+    }
+
+    #[test]
+    fn test_small_capacity() {
+        let rb = LockFreeRingBuffer::new(1);
+        assert_eq!(rb.capacity(), 1);
+        assert_eq!(rb.write("ab"), Ok(1));
+        assert_eq!(rb.write("я"), Err(WriteError::NoSpaceLeft));
+        assert_eq!(rb.read(1), Some("a".to_string()));
+
+        let rb = LockFreeRingBuffer::new(0);
+        assert_eq!(rb.write("a"), Err(WriteError::NoSpaceLeft));
+        assert_eq!(rb.read(1), None);
+    }
+
+    #[test]
+    fn test_multithread_write_read() {
+        let rb = LockFreeRingBuffer::new(64);
+        let num_threads = 10;
+        let items_per_thread = 10_000;
+
+        let counts = scope(|s| {
+            for i in 0..num_threads {
+                let rb = rb.clone();
+                s.spawn(move || {
+                    // Многобайтовый символ проверяет, что символы не разрезаются
+                    let item = ['0', 'я', '🦀', 'z'][i % 4].to_string();
+                    for _ in 0..items_per_thread {
+                        while rb.write(&item).is_err() {
+                            yield_now();
+                        }
+                    }
+                });
+            }
+
+            let readers: Vec<_> = (0..num_threads)
+                .map(|_| {
+                    let rb = rb.clone();
+                    s.spawn(move || {
+                        let mut reads = Vec::with_capacity(items_per_thread);
+                        while reads.len() < items_per_thread {
+                            match rb.read(1) {
+                                Some(read) => reads.push(read),
+                                None => yield_now(),
+                            }
+                        }
+                        reads
+                    })
+                })
+                .collect();
+
+            let mut counts = HashMap::new();
+            for reader in readers {
+                for item in reader.join().unwrap() {
+                    *counts.entry(item).or_insert(0) += 1;
+                }
+            }
+            counts
+        });
+
+        // Ни один символ не потерян и не прочитан дважды
+        assert_eq!(counts["0"], 3 * items_per_thread);
+        assert_eq!(counts["я"], 3 * items_per_thread);
+        assert_eq!(counts["🦀"], 2 * items_per_thread);
+        assert_eq!(counts["z"], 2 * items_per_thread);
+        assert!(rb.is_empty());
+    }
+}
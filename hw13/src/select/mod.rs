@@ -0,0 +1,274 @@
+// Ожидание сразу нескольких ThreadSafeRingBuffer: Select ждёт, пока в любом из
+// зарегистрированных буферов не появятся данные (или место для записи), и
+// возвращает номер готовой операции.
+//
+// Каждый Select заводит свой Signal - счётчик событий с Condvar - и регистрирует его
+// во всех своих буферах; буфер будит его при каждой записи, чтении и закрытии.
+// Перед проверкой буферов запоминается значение счётчика, и заснуть можно, только
+// если оно не изменилось, поэтому событие между проверкой и ожиданием не теряется.
+//
+// Готовность проверяется по кругу, начиная со следующей операции после выбранной
+// в прошлый раз, так что постоянно занятый буфер не заслоняет остальные.
+// Select только сообщает о готовности: данные может успеть забрать другой поток,
+// поэтому саму операцию лучше делать неждущими read/write.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::ThreadSafeRingBuffer;
+
+#[derive(Debug, PartialEq)]
+pub enum SelectError {
+    Timeout,
+}
+
+pub(crate) struct Signal {
+    events: Mutex<u64>,
+    condvar: Condvar,
+}
+
+impl Signal {
+    pub(crate) fn notify(&self) {
+        *self.events.lock().unwrap() += 1;
+        self.condvar.notify_all();
+    }
+
+    fn events(&self) -> u64 {
+        *self.events.lock().unwrap()
+    }
+
+    // Ждёт, пока счётчик не уйдёт от seen; возвращает true, если срок истёк
+    fn wait(&self, seen: u64, deadline: Option<Instant>) -> bool {
+        let mut events = self.events.lock().unwrap();
+        while *events == seen {
+            match deadline {
+                None => events = self.condvar.wait(events).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return true;
+                    }
+                    events = self.condvar.wait_timeout(events, deadline - now).unwrap().0;
+                }
+            }
+        }
+        false
+    }
+}
+
+enum Op {
+    Recv,
+    Send,
+}
+
+pub struct Select<'a> {
+    ops: Vec<(&'a ThreadSafeRingBuffer, Op)>,
+    signal: Arc<Signal>,
+    // С какой операции начнётся следующая проверка
+    next: usize,
+}
+
+impl<'a> Select<'a> {
+    pub fn new() -> Self {
+        Select {
+            ops: Vec::new(),
+            signal: Arc::new(Signal {
+                events: Mutex::new(0),
+                condvar: Condvar::new(),
+            }),
+            next: 0,
+        }
+    }
+
+    // Ждать данных в tsrb; возвращает номер операции. Закрытый буфер тоже считается
+    // готовым, чтобы читатель узнал о конце данных.
+    pub fn recv(&mut self, tsrb: &'a ThreadSafeRingBuffer) -> usize {
+        self.add(tsrb, Op::Recv)
+    }
+
+    // Ждать места в tsrb; закрытый буфер тоже готов - запись в него сразу вернёт Closed
+    pub fn send(&mut self, tsrb: &'a ThreadSafeRingBuffer) -> usize {
+        self.add(tsrb, Op::Send)
+    }
+
+    // Ждёт, пока одна из операций не станет готова, и возвращает её номер
+    pub fn select(&mut self) -> usize {
+        self.select_until(None).expect("ожидание без таймаута")
+    }
+
+    pub fn select_timeout(&mut self, timeout: Duration) -> Result<usize, SelectError> {
+        self.select_until(Some(Instant::now() + timeout))
+    }
+
+    // Номер готовой операции, если такая есть прямо сейчас
+    pub fn try_select(&mut self) -> Option<usize> {
+        let count = self.ops.len();
+        let ready = (0..count)
+            .map(|i| (self.next + i) % count)
+            .find(|&i| self.is_ready(i))?;
+        self.next = (ready + 1) % count;
+        Some(ready)
+    }
+
+    fn select_until(&mut self, deadline: Option<Instant>) -> Result<usize, SelectError> {
+        assert!(!self.ops.is_empty(), "в Select нет ни одной операции");
+
+        loop {
+            let seen = self.signal.events();
+            if let Some(ready) = self.try_select() {
+                return Ok(ready);
+            }
+            if self.signal.wait(seen, deadline) {
+                return Err(SelectError::Timeout);
+            }
+        }
+    }
+
+    fn add(&mut self, tsrb: &'a ThreadSafeRingBuffer, op: Op) -> usize {
+        tsrb.inner.selects.lock().unwrap().push(self.signal.clone());
+        self.ops.push((tsrb, op));
+        self.ops.len() - 1
+    }
+
+    fn is_ready(&self, i: usize) -> bool {
+        let (tsrb, op) = &self.ops[i];
+        let state = tsrb.lock();
+        state.closed
+            || match op {
+                Op::Recv => !state.rb.is_empty(),
+                Op::Send => state.writers.is_empty() && state.rb.fits(1),
+            }
+    }
+}
+
+impl Default for Select<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Select<'_> {
+    fn drop(&mut self) {
+        for (tsrb, _) in &self.ops {
+            // Один буфер может быть зарегистрирован несколько раз: убираем все записи
+            tsrb.inner
+                .selects
+                .lock()
+                .unwrap()
+                .retain(|signal| !Arc::ptr_eq(signal, &self.signal));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::{scope, sleep};
+
+    #[test]
+    fn test_ready_recv() {
+        let first = ThreadSafeRingBuffer::new(4);
+        let second = ThreadSafeRingBuffer::new(4);
+        let mut sel = Select::new();
+        assert_eq!(sel.recv(&first), 0);
+        assert_eq!(sel.recv(&second), 1);
+
+        assert_eq!(sel.try_select(), None);
+        let _ = second.write("a");
+        assert_eq!(sel.select(), 1);
+        assert_eq!(second.read(1), Some("a".to_string()));
+        assert_eq!(
+            sel.select_timeout(Duration::from_millis(10)),
+            Err(SelectError::Timeout)
+        );
+
+        first.close();
+        assert_eq!(sel.select(), 0);
+    }
+
+    #[test]
+    fn test_ready_send() {
+        let tsrb = ThreadSafeRingBuffer::new(1);
+        let mut sel = Select::new();
+        sel.send(&tsrb);
+        assert_eq!(sel.try_select(), Some(0));
+
+        let _ = tsrb.write("a");
+        assert_eq!(sel.try_select(), None);
+        let _ = tsrb.read(1);
+        assert_eq!(sel.try_select(), Some(0));
+    }
+
+    #[test]
+    fn test_fair() {
+        let buffers: Vec<_> = (0..3).map(|_| ThreadSafeRingBuffer::new(4)).collect();
+        let mut sel = Select::new();
+        for tsrb in &buffers {
+            let _ = tsrb.write("x");
+            sel.recv(tsrb);
+        }
+
+        // Все буферы готовы, и ни один не читается: выбор идёт по кругу
+        let chosen: Vec<usize> = (0..6).map(|_| sel.select()).collect();
+        assert_eq!(chosen, [0, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_wakes_up() {
+        let first = ThreadSafeRingBuffer::new(4);
+        let second = ThreadSafeRingBuffer::new(4);
+
+        scope(|s| {
+            let selector = s.spawn(|| {
+                let mut sel = Select::new();
+                sel.recv(&first);
+                sel.recv(&second);
+                sel.select()
+            });
+            sleep(Duration::from_millis(20));
+            let _ = second.write("a");
+            assert_eq!(selector.join().unwrap(), 1);
+        });
+
+        // Удалённый Select снимает регистрацию
+        assert!(second.inner.selects.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_multithread_select() {
+        let buffers: Vec<_> = (0..4).map(|_| ThreadSafeRingBuffer::new(8)).collect();
+        let items_per_buffer = 1000;
+
+        let received = scope(|s| {
+            for tsrb in &buffers {
+                let writer = tsrb.writer();
+                s.spawn(move || {
+                    for _ in 0..items_per_buffer {
+                        writer.write_blocking("x").unwrap();
+                    }
+                });
+            }
+
+            let mut sel = Select::new();
+            for tsrb in &buffers {
+                sel.recv(tsrb);
+            }
+
+            let mut received = vec![0; buffers.len()];
+            let mut done = vec![false; buffers.len()];
+            while done.contains(&false) {
+                let i = sel.select();
+                match buffers[i].read(8) {
+                    Some(read) => received[i] += read.len(),
+                    // Закрытый буфер остаётся готовым и дальше, но выбор идёт по кругу,
+                    // так что остальные буферы всё равно дочитываются
+                    None if buffers[i].is_closed() => done[i] = true,
+                    None => {}
+                }
+            }
+            received
+        });
+
+        assert!(received.iter().all(|&count| count == items_per_buffer));
+    }
+}
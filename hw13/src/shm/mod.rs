@@ -0,0 +1,743 @@
+// Кольцевой буфер в разделяемой памяти POSIX (shm_open + mmap) для обмена текстом
+// между процессами: один процесс пишет, другой читает, как через pipe.
+//
+// В начале отображения лежит управляющий блок с атомарными позициями чтения и записи,
+// за ним - данные. Писатель двигает только tail, читатель - только head. Ожидание
+// построено на futex: после каждой записи писатель увеличивает счётчик data_seq
+// и будит ждущих на нём, а читатель так же поступает с space_seq. Ждущий засыпает,
+// только если счётчик не изменился с момента проверки, поэтому сигнал не теряется.
+//
+// Схема верна только для одного писателя и одного читателя, поэтому сегмент делится
+// на половины ShmWriter и ShmReader. Роль занимается через compare_exchange в
+// управляющем блоке, так что второго писателя или читателя не будет и в другом
+// процессе, а методы половин берут &mut self и не дают делить одну половину между
+// потоками. Удаление половины закрывает поток: читатель дочитывает остатки и получает
+// Closed, писатель сразу получает Closed. Если процесс упал, не удалив свою половину,
+// другая сторона об этом не узнает и будет ждать дальше.
+//
+// Управляющий блок лежит в памяти, которую может испортить любой процесс с доступом
+// к сегменту. Если позиции разошлись больше чем на ёмкость или в данных не UTF-8,
+// сегмент закрывается с обеих сторон, а не читается мимо данных.
+//
+// Как и в ThreadSafeRingBuffer, символы UTF-8 записываются и читаются только целиком.
+
+use std::ffi::CString;
+use std::io;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::{ReadError, Utf8Tail, WriteError};
+
+const MAGIC: u64 = u64::from_le_bytes(*b"hw13-shm");
+
+// Состояние роли писателя или читателя в управляющем блоке
+const FREE: u32 = 0;
+const ATTACHED: u32 = 1;
+const CLOSED: u32 = 2;
+
+#[repr(C, align(64))]
+struct Padded<T>(T);
+
+#[repr(C)]
+struct Control {
+    // Ставится последним при создании: открывающий видит либо готовый блок, либо чужой
+    magic: AtomicU64,
+    capacity: u64,
+    writer: AtomicU32,
+    reader: AtomicU32,
+    head: Padded<AtomicU64>,
+    tail: Padded<AtomicU64>,
+    data_seq: Padded<AtomicU32>,
+    space_seq: Padded<AtomicU32>,
+}
+
+const DATA_OFFSET: usize = std::mem::size_of::<Control>();
+
+// Удаляет имя сегмента; уже открытые отображения продолжают работать
+pub fn unlink(name: &str) -> io::Result<()> {
+    let name = CString::new(name).map_err(|_| io::ErrorKind::InvalidInput)?;
+    if unsafe { libc::shm_unlink(name.as_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub struct ShmWriter {
+    segment: Segment,
+}
+
+impl ShmWriter {
+    // Создаёт новый сегмент name (например, "/hw13-ring") на capacity байт данных
+    pub fn create(name: &str, capacity: usize) -> io::Result<Self> {
+        let segment = Segment::create(name, capacity)?.attach(Role::Writer)?;
+        Ok(ShmWriter { segment })
+    }
+
+    // Подключается писателем к сегменту, созданному другим процессом
+    pub fn open(name: &str) -> io::Result<Self> {
+        let segment = Segment::open(name)?.attach(Role::Writer)?;
+        Ok(ShmWriter { segment })
+    }
+
+    pub fn write(&mut self, buf: &str) -> Result<usize, WriteError> {
+        let segment = &self.segment;
+        let control = segment.control();
+        if control.reader.load(Ordering::Acquire) == CLOSED {
+            return Err(WriteError::Closed);
+        }
+
+        let tail = control.tail.0.load(Ordering::Relaxed);
+        let head = control.head.0.load(Ordering::Acquire);
+        let Some(len) = segment.filled(head, tail) else {
+            segment.close();
+            return Err(WriteError::Closed);
+        };
+        let available = segment.capacity() - len;
+
+        let mut end = std::cmp::min(buf.len(), available);
+        while !buf.is_char_boundary(end) {
+            end -= 1;
+        }
+        if end == 0 {
+            // Если не поместился ни один символ, считаем, что места нет
+            return if buf.is_empty() {
+                Ok(0)
+            } else {
+                Err(WriteError::NoSpaceLeft)
+            };
+        }
+
+        segment.copy_in(tail, &buf.as_bytes()[..end]);
+        control
+            .tail
+            .0
+            .store(tail.wrapping_add(end as u64), Ordering::Release);
+        signal(&control.data_seq.0);
+        Ok(end)
+    }
+
+    // Ждёт, пока в буфер не поместится хотя бы первый символ строки
+    pub fn write_blocking(&mut self, buf: &str) -> Result<usize, WriteError> {
+        self.write_until(buf, None)
+    }
+
+    pub fn write_timeout(&mut self, buf: &str, timeout: Duration) -> Result<usize, WriteError> {
+        self.write_until(buf, Some(Instant::now() + timeout))
+    }
+
+    pub fn len(&self) -> usize {
+        self.segment.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn available(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.segment.capacity()
+    }
+
+    fn write_until(&mut self, buf: &str, deadline: Option<Instant>) -> Result<usize, WriteError> {
+        let first = buf.chars().next().map_or(0, char::len_utf8);
+        if first > self.capacity() {
+            return Err(WriteError::NoSpaceLeft);
+        }
+
+        loop {
+            let seq = self.segment.control().space_seq.0.load(Ordering::Acquire);
+            match self.write(buf) {
+                Err(WriteError::NoSpaceLeft) => {}
+                result => return result,
+            }
+            if !wait(&self.segment.control().space_seq.0, seq, deadline) {
+                return Err(WriteError::Timeout);
+            }
+        }
+    }
+}
+
+impl Drop for ShmWriter {
+    fn drop(&mut self) {
+        self.segment.detach(Role::Writer);
+    }
+}
+
+pub struct ShmReader {
+    segment: Segment,
+    // Начало символа, остальные байты которого ещё не записаны
+    tail: Utf8Tail,
+}
+
+impl ShmReader {
+    pub fn create(name: &str, capacity: usize) -> io::Result<Self> {
+        let segment = Segment::create(name, capacity)?.attach(Role::Reader)?;
+        Ok(ShmReader {
+            segment,
+            tail: Utf8Tail::default(),
+        })
+    }
+
+    // Подключается читателем к сегменту, созданному другим процессом
+    pub fn open(name: &str) -> io::Result<Self> {
+        let segment = Segment::open(name)?.attach(Role::Reader)?;
+        Ok(ShmReader {
+            segment,
+            tail: Utf8Tail::default(),
+        })
+    }
+
+    // Читает не больше b байт, не разрезая символы; из пустого буфера возвращает None.
+    // Если первый символ длиннее b, он всё равно читается целиком.
+    pub fn read(&mut self, b: usize) -> Option<String> {
+        let segment = &self.segment;
+        let control = segment.control();
+        let head = control.head.0.load(Ordering::Relaxed);
+        let Some(len) = segment.filled(head, control.tail.0.load(Ordering::Acquire)) else {
+            segment.close();
+            return None;
+        };
+        if len == 0 {
+            return None;
+        }
+
+        let starts_char = |i: usize| !is_continuation(segment.byte_at(head + i as u64));
+        let mut end = std::cmp::min(b, len);
+        while end > 0 && end < len && !starts_char(end) {
+            end -= 1;
+        }
+        if end == 0 && b > 0 {
+            end = (1..len).find(|&i| starts_char(i)).unwrap_or(len);
+        }
+
+        let mut bytes = self.tail.as_bytes().to_vec();
+        bytes.extend(segment.copy_out(head, end));
+        control
+            .head
+            .0
+            .store(head.wrapping_add(end as u64), Ordering::Release);
+        signal(&control.space_seq.0);
+
+        // Писатель кладёт только целые символы, но сегмент общий, и оборванный
+        // символ дочитывается в следующий раз, как в io::Write у ThreadSafeRingBuffer
+        self.tail = Utf8Tail::default();
+        let text = match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(error) if error.utf8_error().error_len().is_none() => {
+                let valid = error.utf8_error().valid_up_to();
+                let mut bytes = error.into_bytes();
+                self.tail.extend(&bytes[valid..]);
+                bytes.truncate(valid);
+                String::from_utf8(bytes).expect("префикс до valid_up_to - UTF-8")
+            }
+            Err(_) => {
+                segment.close();
+                return None;
+            }
+        };
+        // Пока символ не дописан, читать нечего
+        if text.is_empty() && b > 0 {
+            return None;
+        }
+        Some(text)
+    }
+
+    // Ждёт данных и читает не больше b байт. Когда писатель удалён и данные
+    // прочитаны, возвращает Closed.
+    pub fn read_blocking(&mut self, b: usize) -> Result<String, ReadError> {
+        self.read_until(b, None)
+    }
+
+    pub fn read_timeout(&mut self, b: usize, timeout: Duration) -> Result<String, ReadError> {
+        self.read_until(b, Some(Instant::now() + timeout))
+    }
+
+    pub fn len(&self) -> usize {
+        self.segment.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.segment.capacity()
+    }
+
+    fn read_until(&mut self, b: usize, deadline: Option<Instant>) -> Result<String, ReadError> {
+        loop {
+            let control = self.segment.control();
+            let seq = control.data_seq.0.load(Ordering::Acquire);
+            // Закрытие проверяется до чтения: писатель ставит его после последней записи
+            let closed = control.writer.load(Ordering::Acquire) == CLOSED;
+            if let Some(read) = self.read(b) {
+                return Ok(read);
+            }
+            if closed {
+                return Err(ReadError::Closed);
+            }
+            if !wait(&self.segment.control().data_seq.0, seq, deadline) {
+                return Err(ReadError::Timeout);
+            }
+        }
+    }
+}
+
+impl Drop for ShmReader {
+    fn drop(&mut self) {
+        self.segment.detach(Role::Reader);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Role {
+    Writer,
+    Reader,
+}
+
+// Отображение сегмента; общее для обеих половин
+struct Segment {
+    ptr: *mut u8,
+    len: usize,
+}
+
+// Отображением владеет одна половина, и переносить её в другой поток можно
+unsafe impl Send for Segment {}
+
+impl Segment {
+    fn create(name: &str, capacity: usize) -> io::Result<Self> {
+        if capacity == 0 {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        let len = DATA_OFFSET
+            .checked_add(capacity)
+            .ok_or(io::ErrorKind::InvalidInput)?;
+        let fd = shm_open(name, libc::O_CREAT | libc::O_EXCL | libc::O_RDWR)?;
+
+        // Сегмент, который не удалось подготовить, не должен остаться под этим именем
+        let segment = if unsafe { libc::ftruncate(fd, len as libc::off_t) } != 0 {
+            let error = io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            Err(error)
+        } else {
+            Self::map(fd, len)
+        };
+        let segment = segment.inspect_err(|_| {
+            let _ = unlink(name);
+        })?;
+
+        // Свежий сегмент заполнен нулями; magic публикует уже записанную ёмкость
+        unsafe { (*segment.control_ptr()).capacity = capacity as u64 };
+        segment.control().magic.store(MAGIC, Ordering::Release);
+        Ok(segment)
+    }
+
+    fn open(name: &str) -> io::Result<Self> {
+        let fd = shm_open(name, libc::O_RDWR)?;
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+            let error = io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(error);
+        }
+
+        let len = stat.st_size as usize;
+        if len < DATA_OFFSET {
+            unsafe { libc::close(fd) };
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+
+        let segment = Self::map(fd, len)?;
+        if segment.control().magic.load(Ordering::Acquire) != MAGIC
+            || segment.capacity() == 0
+            || DATA_OFFSET.checked_add(segment.capacity()) != Some(len)
+        {
+            return Err(io::ErrorKind::InvalidData.into());
+        }
+        Ok(segment)
+    }
+
+    // Занимает роль; если её уже кто-то занимал, возвращает ResourceBusy
+    fn attach(self, role: Role) -> io::Result<Self> {
+        self.role(role)
+            .compare_exchange(FREE, ATTACHED, Ordering::AcqRel, Ordering::Acquire)
+            .map_err(|_| io::Error::from(io::ErrorKind::ResourceBusy))?;
+        Ok(self)
+    }
+
+    // Закрывает поток со своей стороны и будит другую сторону
+    fn detach(&self, role: Role) {
+        self.role(role).store(CLOSED, Ordering::Release);
+        let control = self.control();
+        match role {
+            Role::Writer => signal(&control.data_seq.0),
+            Role::Reader => signal(&control.space_seq.0),
+        }
+    }
+
+    fn role(&self, role: Role) -> &AtomicU32 {
+        match role {
+            Role::Writer => &self.control().writer,
+            Role::Reader => &self.control().reader,
+        }
+    }
+
+    // Испорченный сегмент считается пустым
+    fn len(&self) -> usize {
+        let control = self.control();
+        let head = control.head.0.load(Ordering::Acquire);
+        self.filled(head, control.tail.0.load(Ordering::Acquire))
+            .unwrap_or(0)
+    }
+
+    // Сколько байт между head и tail; None, если позиции не могут быть верными
+    fn filled(&self, head: u64, tail: u64) -> Option<usize> {
+        let len = tail.wrapping_sub(head);
+        (len <= self.capacity() as u64).then_some(len as usize)
+    }
+
+    // Закрывает поток с обеих сторон: данным в сегменте больше нельзя верить
+    fn close(&self) {
+        self.detach(Role::Writer);
+        self.detach(Role::Reader);
+    }
+
+    fn capacity(&self) -> usize {
+        self.control().capacity as usize
+    }
+
+    fn map(fd: libc::c_int, len: usize) -> io::Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            )
+        };
+        // Отображение держит сегмент само, дескриптор больше не нужен
+        let error = io::Error::last_os_error();
+        unsafe { libc::close(fd) };
+        if ptr == libc::MAP_FAILED {
+            return Err(error);
+        }
+
+        Ok(Self {
+            ptr: ptr.cast(),
+            len,
+        })
+    }
+
+    fn control_ptr(&self) -> *mut Control {
+        self.ptr.cast()
+    }
+
+    fn control(&self) -> &Control {
+        unsafe { &*self.control_ptr() }
+    }
+
+    fn byte_at(&self, pos: u64) -> u8 {
+        let slot = (pos % self.capacity() as u64) as usize;
+        unsafe { *self.ptr.add(DATA_OFFSET + slot) }
+    }
+
+    // Данные идут по кругу, поэтому копирование делится максимум на два куска
+    fn copy_in(&self, pos: u64, bytes: &[u8]) {
+        let slot = (pos % self.capacity() as u64) as usize;
+        let first = std::cmp::min(bytes.len(), self.capacity() - slot);
+        unsafe {
+            let data = self.ptr.add(DATA_OFFSET);
+            ptr::copy_nonoverlapping(bytes.as_ptr(), data.add(slot), first);
+            ptr::copy_nonoverlapping(bytes[first..].as_ptr(), data, bytes.len() - first);
+        }
+    }
+
+    fn copy_out(&self, pos: u64, count: usize) -> Vec<u8> {
+        let slot = (pos % self.capacity() as u64) as usize;
+        let first = std::cmp::min(count, self.capacity() - slot);
+        let mut bytes = vec![0; count];
+        unsafe {
+            let data = self.ptr.add(DATA_OFFSET);
+            ptr::copy_nonoverlapping(data.add(slot), bytes.as_mut_ptr(), first);
+            ptr::copy_nonoverlapping(data, bytes[first..].as_mut_ptr(), count - first);
+        }
+        bytes
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr.cast(), self.len) };
+    }
+}
+
+fn shm_open(name: &str, flags: libc::c_int) -> io::Result<libc::c_int> {
+    let name = CString::new(name).map_err(|_| io::ErrorKind::InvalidInput)?;
+    let fd = unsafe { libc::shm_open(name.as_ptr(), flags, 0o600) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(fd)
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+// Сообщает ждущим в любом процессе, что счётчик изменился
+fn signal(seq: &AtomicU32) {
+    seq.fetch_add(1, Ordering::Release);
+    unsafe { libc::syscall(libc::SYS_futex, seq.as_ptr(), libc::FUTEX_WAKE, i32::MAX) };
+}
+
+// Спит, пока счётчик равен seen, но не дольше крайнего срока.
+// Возвращает false, если срок уже истёк.
+fn wait(seq: &AtomicU32, seen: u32, deadline: Option<Instant>) -> bool {
+    let timeout = match deadline {
+        None => None,
+        Some(deadline) => {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            let left = deadline - now;
+            Some(libc::timespec {
+                tv_sec: left.as_secs() as libc::time_t,
+                tv_nsec: left.subsec_nanos() as libc::c_long,
+            })
+        }
+    };
+
+    // Ложные пробуждения и EAGAIN (счётчик уже изменился) не страшны:
+    // вызывающий всё равно перепроверяет условие
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            seq.as_ptr(),
+            libc::FUTEX_WAIT,
+            seen,
+            timeout
+                .as_ref()
+                .map_or(ptr::null(), |timeout| timeout as *const libc::timespec),
+        )
+    };
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Имя сегмента, которое удаляется в конце теста
+    struct Name(String);
+
+    impl Name {
+        fn new(name: &str) -> Self {
+            let name = format!("/hw13-{}-{}", name, std::process::id());
+            let _ = unlink(&name);
+            Name(name)
+        }
+    }
+
+    impl Drop for Name {
+        fn drop(&mut self) {
+            let _ = unlink(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_same_process() {
+        let name = Name::new("local");
+        let mut writer = ShmWriter::create(&name.0, 5).unwrap();
+        let mut reader = ShmReader::open(&name.0).unwrap();
+        assert_eq!(reader.capacity(), 5);
+
+        assert_eq!(writer.write("ёжик"), Ok(4));
+        assert_eq!(writer.write("ы"), Err(WriteError::NoSpaceLeft));
+        assert_eq!(reader.len(), 4);
+        assert_eq!(reader.read(3), Some("ё".to_string()));
+        assert_eq!(writer.write("ab"), Ok(2));
+        assert_eq!(reader.read(10), Some("жab".to_string()));
+        assert_eq!(reader.read(1), None);
+
+        let timeout = Duration::from_millis(10);
+        assert_eq!(reader.read_timeout(1, timeout), Err(ReadError::Timeout));
+        assert_eq!(writer.write("abcde"), Ok(5));
+        assert_eq!(writer.write_timeout("f", timeout), Err(WriteError::Timeout));
+    }
+
+    #[test]
+    fn test_open_missing() {
+        let name = Name::new("missing");
+        assert!(ShmReader::open(&name.0).is_err());
+        let _reader = ShmReader::create(&name.0, 4).unwrap();
+        // Сегмент с таким именем уже есть
+        assert!(ShmWriter::create(&name.0, 4).is_err());
+    }
+
+    #[test]
+    fn test_single_writer_and_reader() {
+        let name = Name::new("roles");
+        let writer = ShmWriter::create(&name.0, 4).unwrap();
+        let busy = ShmWriter::open(&name.0).map(drop).unwrap_err();
+        assert_eq!(busy.kind(), io::ErrorKind::ResourceBusy);
+
+        let _reader = ShmReader::open(&name.0).unwrap();
+        assert!(ShmReader::open(&name.0).is_err());
+        // Удалённого писателя заменить нельзя: поток уже закрыт
+        drop(writer);
+        assert!(ShmWriter::open(&name.0).is_err());
+    }
+
+    #[test]
+    fn test_close() {
+        let name = Name::new("close");
+        let mut writer = ShmWriter::create(&name.0, 4).unwrap();
+        let mut reader = ShmReader::open(&name.0).unwrap();
+
+        assert_eq!(writer.write("abc"), Ok(3));
+        drop(writer);
+        // Остатки дочитываются, после них - конец данных
+        assert_eq!(reader.read_blocking(2), Ok("ab".to_string()));
+        assert_eq!(reader.read_blocking(2), Ok("c".to_string()));
+        assert_eq!(reader.read_blocking(2), Err(ReadError::Closed));
+
+        let name = Name::new("close-reader");
+        let reader = ShmReader::create(&name.0, 1).unwrap();
+        let mut writer = ShmWriter::open(&name.0).unwrap();
+        assert_eq!(writer.write("a"), Ok(1));
+        std::thread::scope(|s| {
+            // Писатель ждёт места и узнаёт, что читателя больше нет
+            let waiting = s.spawn(move || writer.write_blocking("b"));
+            std::thread::sleep(Duration::from_millis(20));
+            drop(reader);
+            assert_eq!(waiting.join().unwrap(), Err(WriteError::Closed));
+        });
+    }
+
+    // Пишет байты в сегмент в обход ShmWriter, как чужой процесс
+    fn write_raw(writer: &ShmWriter, bytes: &[u8]) {
+        let control = writer.segment.control();
+        let tail = control.tail.0.load(Ordering::Relaxed);
+        writer.segment.copy_in(tail, bytes);
+        control
+            .tail
+            .0
+            .store(tail + bytes.len() as u64, Ordering::Release);
+    }
+
+    #[test]
+    fn test_read_whole_chars() {
+        let name = Name::new("chars");
+        let mut writer = ShmWriter::create(&name.0, 8).unwrap();
+        let mut reader = ShmReader::open(&name.0).unwrap();
+
+        // Первый символ отдаётся целиком, даже если он длиннее b
+        assert_eq!(writer.write("жa"), Ok(3));
+        assert_eq!(reader.read(1), Some("ж".to_string()));
+        assert_eq!(reader.read(0), Some(String::new()));
+
+        // Оборванный символ ждёт своего конца
+        let crab = "🦀".as_bytes();
+        write_raw(&writer, &crab[..1]);
+        assert_eq!(reader.read(2), Some("a".to_string()));
+        assert_eq!(reader.read(2), None);
+        write_raw(&writer, &crab[1..]);
+        assert_eq!(reader.read(8), Some("🦀".to_string()));
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_corruption_closes() {
+        let name = Name::new("bad-utf8");
+        let mut writer = ShmWriter::create(&name.0, 8).unwrap();
+        let mut reader = ShmReader::open(&name.0).unwrap();
+        write_raw(&writer, &[b'a', 0xff]);
+        assert_eq!(reader.read(8), None);
+        assert_eq!(reader.read_blocking(8), Err(ReadError::Closed));
+        assert_eq!(writer.write("b"), Err(WriteError::Closed));
+
+        let name = Name::new("bad-positions");
+        let mut writer = ShmWriter::create(&name.0, 8).unwrap();
+        let mut reader = ShmReader::open(&name.0).unwrap();
+        // Позиция записи позади позиции чтения
+        writer.segment.control().head.0.store(5, Ordering::Release);
+        writer.segment.control().tail.0.store(4, Ordering::Release);
+        assert_eq!(reader.len(), 0);
+        assert_eq!(writer.write("a"), Err(WriteError::Closed));
+        assert_eq!(reader.read_blocking(1), Err(ReadError::Closed));
+    }
+
+    #[test]
+    fn test_open_unfinished_segment() {
+        let name = Name::new("unfinished");
+        let writer = ShmWriter::create(&name.0, 4).unwrap();
+        // Создатель ещё не опубликовал magic
+        writer.segment.control().magic.store(0, Ordering::Release);
+        let err = ShmReader::open(&name.0).map(drop).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(ShmWriter::create(&Name::new("empty").0, 0).is_err());
+    }
+
+    #[test]
+    fn test_failed_create_is_unlinked() {
+        let name = Name::new("failed");
+        // Такой размер ftruncate не примет
+        assert!(ShmWriter::create(&name.0, usize::MAX - DATA_OFFSET).is_err());
+        let missing = ShmReader::open(&name.0).map(drop).unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_fork_child_writes() {
+        let name = Name::new("fork");
+        let mut reader = ShmReader::create(&name.0, 16).unwrap();
+        let lines = 1000;
+
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0, "fork: {}", io::Error::last_os_error());
+        if pid == 0 {
+            // Дочерний процесс подключается по имени и пишет, ожидая места
+            let code = match ShmWriter::open(&name.0) {
+                Ok(mut writer) => {
+                    let ok = (0..lines).all(|i| {
+                        let line = format!("строка {}\n", i);
+                        let mut rest = line.as_str();
+                        while !rest.is_empty() {
+                            match writer.write_blocking(rest) {
+                                Ok(written) => rest = &rest[written..],
+                                Err(_) => return false,
+                            }
+                        }
+                        true
+                    });
+                    // _exit не вызывает деструкторы: поток закрывается явно
+                    drop(writer);
+                    if ok { 0 } else { 1 }
+                }
+                Err(_) => 2,
+            };
+            unsafe { libc::_exit(code) };
+        }
+
+        // Читатель не знает, сколько придёт строк: он читает до закрытия
+        let mut received = String::new();
+        while let Ok(read) = reader.read_blocking(16) {
+            received.push_str(&read);
+        }
+
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+        assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+
+        let expected: String = (0..lines).map(|i| format!("строка {}\n", i)).collect();
+        assert_eq!(received, expected);
+        assert!(reader.is_empty());
+    }
+}
@@ -0,0 +1,377 @@
+// Широковещательный кольцевой буфер: каждый элемент получают все подписчики
+// (как в Disruptor).
+//
+// Писатель нумерует элементы по порядку, а у каждого подписчика есть свой курсор -
+// номер следующего элемента, который он прочитает. Элемент с номером seq лежит
+// в ячейке seq % capacity и затирается через capacity записей. Что делать, если
+// самый медленный подписчик отстал на весь буфер, задаёт Mode: писатель либо ждёт
+// его, либо пишет дальше, а отставший подписчик получает Lagged и перескакивает
+// на самый старый уцелевший элемент.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    // Писатель ждёт самого медленного подписчика
+    Block,
+    // Писатель не ждёт, отставшие подписчики теряют элементы
+    Lag,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RecvError {
+    // Подписчик отстал, и столько элементов было затёрто до того, как он их прочитал
+    Lagged(u64),
+    // Писатель удалён и все элементы прочитаны
+    Closed,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TryRecvError {
+    Empty,
+    Lagged(u64),
+    Closed,
+}
+
+struct State<T> {
+    slots: Box<[Option<T>]>,
+    // Номер следующего записываемого элемента
+    head: u64,
+    cursors: HashMap<u64, u64>,
+    next_id: u64,
+    closed: bool,
+}
+
+impl<T> State<T> {
+    // Номер самого старого элемента, который ещё не затёрт
+    fn tail(&self) -> u64 {
+        self.head.saturating_sub(self.slots.len() as u64)
+    }
+
+    fn is_full(&self) -> bool {
+        self.cursors
+            .values()
+            .any(|&cursor| self.head - cursor == self.slots.len() as u64)
+    }
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    mode: Mode,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap()
+    }
+}
+
+pub struct Publisher<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub struct Subscriber<T> {
+    shared: Arc<Shared<T>>,
+    id: u64,
+}
+
+// Создаёт буфер на capacity элементов; подписчики добавляются через Publisher::subscribe
+pub fn channel<T: Clone>(capacity: usize, mode: Mode) -> Publisher<T> {
+    assert!(capacity > 0, "ёмкость буфера должна быть больше 0");
+
+    Publisher {
+        shared: Arc::new(Shared {
+            state: Mutex::new(State {
+                slots: (0..capacity).map(|_| None).collect(),
+                head: 0,
+                cursors: HashMap::new(),
+                next_id: 0,
+                closed: false,
+            }),
+            mode,
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }),
+    }
+}
+
+impl<T: Clone> Publisher<T> {
+    // Записывает элемент для всех подписчиков. В режиме Block ждёт, пока самый
+    // медленный подписчик не освободит ячейку.
+    pub fn publish(&self, item: T) {
+        let mut state = self.shared.lock();
+        while self.shared.mode == Mode::Block && state.is_full() {
+            state = self.shared.not_full.wait(state).unwrap();
+        }
+        self.put(state, item);
+    }
+
+    // Не ждёт: в режиме Block при отставшем подписчике возвращает элемент обратно
+    pub fn try_publish(&self, item: T) -> Result<(), T> {
+        let state = self.shared.lock();
+        if self.shared.mode == Mode::Block && state.is_full() {
+            return Err(item);
+        }
+        self.put(state, item);
+        Ok(())
+    }
+
+    // Новый подписчик начинает с текущей головы и не видит уже записанных элементов
+    pub fn subscribe(&self) -> Subscriber<T> {
+        let mut state = self.shared.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        let head = state.head;
+        state.cursors.insert(id, head);
+
+        Subscriber {
+            shared: self.shared.clone(),
+            id,
+        }
+    }
+
+    pub fn subscribers(&self) -> usize {
+        self.shared.lock().cursors.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.lock().slots.len()
+    }
+
+    fn put(&self, mut state: MutexGuard<'_, State<T>>, item: T) {
+        let idx = (state.head % state.slots.len() as u64) as usize;
+        state.slots[idx] = Some(item);
+        state.head += 1;
+        self.shared.not_empty.notify_all();
+    }
+}
+
+impl<T> Drop for Publisher<T> {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.not_empty.notify_all();
+    }
+}
+
+impl<T: Clone> Subscriber<T> {
+    // Ждёт следующий элемент. После Lagged курсор уже передвинут на самый
+    // старый уцелевший элемент, и следующий вызов вернёт его.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.shared.lock();
+        loop {
+            match self.take(&mut state) {
+                Ok(item) => return Ok(item),
+                Err(TryRecvError::Empty) => state = self.shared.not_empty.wait(state).unwrap(),
+                Err(TryRecvError::Lagged(missed)) => return Err(RecvError::Lagged(missed)),
+                Err(TryRecvError::Closed) => return Err(RecvError::Closed),
+            }
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.take(&mut self.shared.lock())
+    }
+
+    // Сколько элементов ждёт этого подписчика, не считая затёртых
+    pub fn len(&self) -> usize {
+        let state = self.shared.lock();
+        (state.head - state.cursors[&self.id].max(state.tail())) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn take(&self, state: &mut State<T>) -> Result<T, TryRecvError> {
+        let cursor = state.cursors[&self.id];
+        let tail = state.tail();
+        if cursor < tail {
+            state.cursors.insert(self.id, tail);
+            return Err(TryRecvError::Lagged(tail - cursor));
+        }
+        if cursor == state.head {
+            return Err(if state.closed {
+                TryRecvError::Closed
+            } else {
+                TryRecvError::Empty
+            });
+        }
+
+        let idx = (cursor % state.slots.len() as u64) as usize;
+        let item = state.slots[idx].clone().expect("ячейка уже записана");
+        state.cursors.insert(self.id, cursor + 1);
+        self.shared.not_full.notify_all();
+        Ok(item)
+    }
+}
+
+impl<T> Drop for Subscriber<T> {
+    fn drop(&mut self) {
+        self.shared.lock().cursors.remove(&self.id);
+        // Удалённый подписчик мог быть самым медленным
+        self.shared.not_full.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::VecDeque;
+    use std::thread;
+
+    #[test]
+    fn test_every_subscriber_gets_every_item() {
+        let publisher = channel(4, Mode::Block);
+        let first = publisher.subscribe();
+        let second = publisher.subscribe();
+        publisher.publish(String::from("a"));
+        publisher.publish(String::from("b"));
+
+        assert_eq!(first.recv(), Ok(String::from("a")));
+        assert_eq!(first.recv(), Ok(String::from("b")));
+        assert_eq!(first.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(second.len(), 2);
+        assert_eq!(second.recv(), Ok(String::from("a")));
+        assert_eq!(second.recv(), Ok(String::from("b")));
+    }
+
+    #[test]
+    fn test_subscribe_at_head() {
+        let publisher = channel(4, Mode::Block);
+        let early = publisher.subscribe();
+        publisher.publish(1);
+        let late = publisher.subscribe();
+        publisher.publish(2);
+
+        assert_eq!(early.try_recv(), Ok(1));
+        assert_eq!(early.try_recv(), Ok(2));
+        assert_eq!(late.try_recv(), Ok(2));
+        assert_eq!(late.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn test_block_waits_for_slowest() {
+        let publisher = channel(2, Mode::Block);
+        let fast = publisher.subscribe();
+        let slow = publisher.subscribe();
+        publisher.publish(1);
+        publisher.publish(2);
+        assert_eq!(fast.try_recv(), Ok(1));
+        assert_eq!(fast.try_recv(), Ok(2));
+        // Быстрый всё прочитал, но медленный ещё держит обе ячейки
+        assert_eq!(publisher.try_publish(3), Err(3));
+
+        assert_eq!(slow.try_recv(), Ok(1));
+        assert_eq!(publisher.try_publish(3), Ok(()));
+        drop(slow);
+        assert_eq!(publisher.subscribers(), 1);
+        assert_eq!(publisher.try_publish(4), Ok(()));
+        assert_eq!(fast.try_recv(), Ok(3));
+    }
+
+    #[test]
+    fn test_lag() {
+        let publisher = channel(2, Mode::Lag);
+        let subscriber = publisher.subscribe();
+        for i in 0..5 {
+            assert_eq!(publisher.try_publish(i), Ok(()));
+        }
+
+        assert_eq!(subscriber.len(), 2);
+        assert_eq!(subscriber.recv(), Err(RecvError::Lagged(3)));
+        assert_eq!(subscriber.recv(), Ok(3));
+        assert_eq!(subscriber.recv(), Ok(4));
+        assert_eq!(subscriber.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn test_closed() {
+        let publisher = channel(2, Mode::Block);
+        let subscriber = publisher.subscribe();
+        publisher.publish(1);
+        drop(publisher);
+
+        assert_eq!(subscriber.recv(), Ok(1));
+        assert_eq!(subscriber.recv(), Err(RecvError::Closed));
+        assert_eq!(subscriber.try_recv(), Err(TryRecvError::Closed));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_capacity() {
+        channel::<u8>(0, Mode::Block);
+    }
+
+    #[test]
+    fn test_threads_keep_order() {
+        let total = 10_000u64;
+        let publisher = channel(16, Mode::Block);
+        let subscribers: Vec<_> = (0..4).map(|_| publisher.subscribe()).collect();
+
+        thread::scope(|s| {
+            for subscriber in subscribers {
+                s.spawn(move || {
+                    let mut expected = 0;
+                    while let Ok(i) = subscriber.recv() {
+                        assert_eq!(i, expected);
+                        expected += 1;
+                    }
+                    assert_eq!(expected, total);
+                });
+            }
+
+            for i in 0..total {
+                publisher.publish(i);
+            }
+            drop(publisher);
+        });
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Publish(u8),
+        // Чтение подписчиком с этим номером
+        Recv(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            any::<u8>().prop_map(Op::Publish),
+            (0..3usize).prop_map(Op::Recv),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_vec_deque(size in 1..8usize, ops in prop::collection::vec(op(), 0..64)) {
+            // В режиме Block каждый подписчик видит ровно то, что записано после его
+            // подписки, а писатель ограничен самым длинным хвостом
+            let publisher = channel(size, Mode::Block);
+            let subscribers: Vec<_> = (0..3).map(|_| publisher.subscribe()).collect();
+            let mut models = vec![VecDeque::new(); 3];
+
+            for op in ops {
+                match op {
+                    Op::Publish(item) => {
+                        let fits = models.iter().all(|model| model.len() < size);
+                        prop_assert_eq!(publisher.try_publish(item).is_ok(), fits);
+                        if fits {
+                            models.iter_mut().for_each(|model| model.push_back(item));
+                        }
+                    }
+                    Op::Recv(i) => {
+                        let expected = models[i].pop_front().ok_or(TryRecvError::Empty);
+                        prop_assert_eq!(subscribers[i].try_recv(), expected);
+                    }
+                }
+                for (subscriber, model) in subscribers.iter().zip(&models) {
+                    prop_assert_eq!(subscriber.len(), model.len());
+                }
+            }
+        }
+    }
+}
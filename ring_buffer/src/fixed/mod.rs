@@ -0,0 +1,564 @@
+// Кольцевой буфер фиксированной ёмкости N без выделения памяти: элементы лежат
+// во встроенном массиве [MaybeUninit<T>; N], поэтому буфер можно положить на стек,
+// а new и with_policy - const fn, так что и в static. Массив не требует от T ни
+// Default, ни Copy: инициализированы только ячейки между read_idx и write_idx.
+// Модуль не использует std и доступен, даже когда крейт собран без фичи std.
+//
+// API повторяет RingBuffer, кроме того, что требует кучи: read(n) возвращает Vec
+// и есть только с фичей std, а ёмкость не меняется, так что resize нет, а политика
+// Grow ведёт себя как Reject. Метрики не собираются.
+//
+// Нулевая ёмкость запрещена на этапе компиляции: StaticRingBuffer::<T, 0>::new()
+// не соберётся.
+
+use core::cmp;
+use core::fmt;
+use core::mem::{self, MaybeUninit};
+use core::slice;
+
+use crate::OverflowPolicy;
+
+/// Нулевая ёмкость - ошибка компиляции:
+///
+/// ```compile_fail
+/// let rb = ring_buffer::StaticRingBuffer::<u8, 0>::new();
+/// ```
+///
+/// ```
+/// let rb = ring_buffer::StaticRingBuffer::<u8, 1>::new();
+/// assert_eq!(rb.capacity(), 1);
+/// ```
+pub struct StaticRingBuffer<T, const N: usize> {
+    read_idx: usize,
+    write_idx: usize,
+    is_full: bool,
+    data: [MaybeUninit<T>; N],
+    policy: OverflowPolicy,
+    dropped: u64,
+}
+
+impl<T, const N: usize> StaticRingBuffer<T, N> {
+    const NOT_EMPTY: () = assert!(N > 0, "ёмкость StaticRingBuffer должна быть больше 0");
+
+    // OverflowPolicy::default() не const, поэтому политика по умолчанию указана явно
+    pub const fn new() -> Self {
+        Self::with_policy(OverflowPolicy::Reject)
+    }
+
+    pub const fn with_policy(policy: OverflowPolicy) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::NOT_EMPTY;
+
+        StaticRingBuffer {
+            read_idx: 0,
+            write_idx: 0,
+            is_full: false,
+            data: [const { MaybeUninit::uninit() }; N],
+            policy,
+            dropped: 0,
+        }
+    }
+
+    // Добавляет элемент в конец очереди; если места нет, возвращает его обратно.
+    // В режиме перезаписи вместо этого вытесняется самый старый элемент.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.is_full {
+            if self.policy != OverflowPolicy::OverwriteOldest {
+                return Err(item);
+            }
+            self.discard(1);
+        }
+
+        self.data[self.write_idx].write(item);
+        self.write_idx = (self.write_idx + 1) % N;
+        self.is_full = self.write_idx == self.read_idx;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        // Ячейка сразу считается свободной, поэтому значение из неё можно забрать
+        let item = unsafe { self.data[self.read_idx].assume_init_read() };
+        self.read_idx = (self.read_idx + 1) % N;
+        self.is_full = false;
+        Some(item)
+    }
+}
+
+impl<T, const N: usize> Default for StaticRingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy, const N: usize> StaticRingBuffer<T, N> {
+    // Записывает столько элементов из buf, сколько помещается, и возвращает их количество.
+    // В режиме перезаписи buf принимается целиком, как у RingBuffer.
+    pub fn write(&mut self, buf: &[T]) -> usize {
+        if self.policy == OverflowPolicy::OverwriteOldest {
+            let skipped = buf.len().saturating_sub(N);
+            let overflow = (buf.len() - skipped).saturating_sub(self.available());
+            self.discard(overflow);
+            self.dropped += skipped as u64;
+            self.write_available(&buf[skipped..]);
+            return buf.len();
+        }
+
+        self.write_available(buf)
+    }
+
+    // Читает не больше n элементов: если в буфере меньше, возвращается только то, что есть
+    #[cfg(feature = "std")]
+    pub fn read(&mut self, n: usize) -> Vec<T> {
+        let (first, second) = self.peek(n);
+        let result = [first, second].concat();
+        self.consume(result.len());
+        result
+    }
+
+    // Копирует элементы в out и возвращает их количество
+    pub fn read_into(&mut self, out: &mut [T]) -> usize {
+        let (first, second) = self.peek(out.len());
+        out[..first.len()].copy_from_slice(first);
+        out[first.len()..first.len() + second.len()].copy_from_slice(second);
+
+        let count = first.len() + second.len();
+        self.consume(count);
+        count
+    }
+
+    fn write_available(&mut self, buf: &[T]) -> usize {
+        let count = cmp::min(buf.len(), self.available());
+        if count == 0 {
+            return 0;
+        }
+
+        // Запись идёт максимум двумя кусками: до конца массива и с его начала
+        let first = cmp::min(count, N - self.write_idx);
+        fill(
+            &mut self.data[self.write_idx..self.write_idx + first],
+            &buf[..first],
+        );
+        fill(&mut self.data[..count - first], &buf[first..count]);
+
+        self.write_idx = (self.write_idx + count) % N;
+        self.is_full = self.write_idx == self.read_idx;
+        count
+    }
+}
+
+impl<T, const N: usize> StaticRingBuffer<T, N> {
+    // Количество элементов, записанных в буфер и ещё не прочитанных
+    pub fn len(&self) -> usize {
+        if self.is_full {
+            N
+        } else if self.write_idx >= self.read_idx {
+            self.write_idx - self.read_idx
+        } else {
+            self.write_idx + N - self.read_idx
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Элемент на позиции i от начала очереди без извлечения
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len() {
+            return None;
+        }
+
+        Some(unsafe { self.data[(self.read_idx + i) % N].assume_init_ref() })
+    }
+
+    // Непрочитанные элементы в виде двух непрерывных кусков: до конца массива
+    // и с его начала. Второй кусок пуст, если данные не переходят через край.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let len = self.len();
+        let first = cmp::min(len, N - self.read_idx);
+        // Непрочитанные ячейки инициализированы
+        unsafe {
+            (
+                assume_init(&self.data[self.read_idx..self.read_idx + first]),
+                assume_init(&self.data[..len - first]),
+            )
+        }
+    }
+
+    // Первые n элементов (или меньше, если столько нет) без извлечения и копирования
+    pub fn peek(&self, n: usize) -> (&[T], &[T]) {
+        let (first, second) = self.as_slices();
+        if n <= first.len() {
+            (&first[..n], &[])
+        } else {
+            (first, &second[..cmp::min(n - first.len(), second.len())])
+        }
+    }
+
+    // Пропускает не больше n элементов без копирования и возвращает их количество
+    pub fn consume(&mut self, n: usize) -> usize {
+        let count = cmp::min(n, self.len());
+        if count == 0 {
+            return 0;
+        }
+
+        if !mem::needs_drop::<T>() {
+            self.read_idx = (self.read_idx + count) % N;
+            self.is_full = false;
+            return count;
+        }
+
+        // Ячейка освобождается до удаления элемента: если drop запаникует,
+        // буфер не удалит этот элемент второй раз
+        for _ in 0..count {
+            let idx = self.read_idx;
+            self.read_idx = (self.read_idx + 1) % N;
+            self.is_full = false;
+            unsafe { self.data[idx].assume_init_drop() };
+        }
+        count
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.is_full
+    }
+
+    // Сколько элементов ещё можно записать
+    pub fn available(&self) -> usize {
+        N - self.len()
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    // Сколько элементов было потеряно из-за перезаписи
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn read_idx(&self) -> usize {
+        self.read_idx
+    }
+
+    pub fn write_idx(&self) -> usize {
+        self.write_idx
+    }
+
+    fn discard(&mut self, n: usize) {
+        self.dropped += self.consume(n) as u64;
+    }
+}
+
+impl<T, const N: usize> Drop for StaticRingBuffer<T, N> {
+    fn drop(&mut self) {
+        self.consume(self.len());
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for StaticRingBuffer<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Неинициализированные ячейки выводить нельзя, поэтому вместо data - два куска
+        // непрочитанных элементов
+        f.debug_struct("StaticRingBuffer")
+            .field("read_idx", &self.read_idx)
+            .field("write_idx", &self.write_idx)
+            .field("is_full", &self.is_full)
+            .field("items", &self.as_slices())
+            .field("policy", &self.policy)
+            .field("dropped", &self.dropped)
+            .finish()
+    }
+}
+
+fn fill<T: Copy>(slots: &mut [MaybeUninit<T>], items: &[T]) {
+    for (slot, &item) in slots.iter_mut().zip(items) {
+        slot.write(item);
+    }
+}
+
+// Безопасно, только если все ячейки slots инициализированы
+unsafe fn assume_init<T>(slots: &[MaybeUninit<T>]) -> &[T] {
+    unsafe { slice::from_raw_parts(slots.as_ptr().cast(), slots.len()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseError;
+    use std::collections::VecDeque;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    // Тесты повторяют тесты RingBuffer, но читают через read_into, чтобы
+    // проходить и без фичи std
+    fn read<const N: usize>(rb: &mut StaticRingBuffer<u8, N>, n: usize) -> Vec<u8> {
+        let mut out = vec![0; n];
+        let count = rb.read_into(&mut out);
+        out.truncate(count);
+        out
+    }
+
+    #[test]
+    fn test_create_buffer() {
+        let rb: StaticRingBuffer<u8, 3> = StaticRingBuffer::new();
+        assert_eq!(rb.capacity(), 3);
+        assert!(rb.is_empty());
+        assert!(!rb.is_full());
+        // Элементы лежат прямо в структуре
+        assert!(std::mem::size_of_val(&rb) < 64);
+    }
+
+    // new - const fn, поэтому буфер создаётся прямо в static
+    static SHARED: std::sync::Mutex<StaticRingBuffer<u8, 4>> =
+        std::sync::Mutex::new(StaticRingBuffer::new());
+
+    #[test]
+    fn test_static() {
+        std::thread::spawn(|| SHARED.lock().unwrap().write(b"abc"))
+            .join()
+            .unwrap();
+        let mut rb = SHARED.lock().unwrap();
+        assert_eq!(rb.capacity(), 4);
+        assert_eq!(read(&mut rb, 4), b"abc");
+    }
+
+    #[test]
+    fn test_drops_items() {
+        use std::rc::Rc;
+
+        let item = Rc::new(());
+        let mut rb = StaticRingBuffer::<_, 3>::with_policy(OverflowPolicy::OverwriteOldest);
+        for _ in 0..4 {
+            rb.push(item.clone()).unwrap();
+        }
+        // Вытесненный элемент удалён сразу
+        assert_eq!(Rc::strong_count(&item), 4);
+        rb.consume(1);
+        assert_eq!(Rc::strong_count(&item), 3);
+        drop(rb.pop());
+        assert_eq!(Rc::strong_count(&item), 2);
+
+        drop(rb);
+        assert_eq!(Rc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut rb = StaticRingBuffer::<_, 2>::new();
+        assert_eq!(rb.push(String::from("a")), Ok(()));
+        assert_eq!(rb.push(String::from("b")), Ok(()));
+        assert_eq!(rb.push(String::from("c")), Err(String::from("c")));
+        assert!(rb.is_full());
+
+        assert_eq!(rb.pop(), Some(String::from("a")));
+        assert_eq!(rb.push(String::from("d")), Ok(()));
+        assert_eq!(rb.pop(), Some(String::from("b")));
+        assert_eq!(rb.pop(), Some(String::from("d")));
+        assert_eq!(rb.pop(), None);
+    }
+
+    #[test]
+    fn test_structs() {
+        let mut rb = StaticRingBuffer::<_, 3>::new();
+        let points = [Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+        assert_eq!(rb.write(&points), 2);
+        assert_eq!(rb.pop(), Some(points[0]));
+        let mut out = [Point::default(); 5];
+        assert_eq!(rb.read_into(&mut out), 1);
+        assert_eq!(out[0], points[1]);
+    }
+
+    #[test]
+    fn test_rw_wrap_around() {
+        let mut rb = StaticRingBuffer::<u8, 3>::new();
+
+        assert_eq!(rb.write(b"ab"), 2);
+        assert_eq!(rb.write_idx(), 2);
+
+        assert_eq!(rb.write(b"cd"), 1);
+        assert_eq!(rb.write_idx(), 0);
+        assert!(rb.is_full());
+
+        assert_eq!(read(&mut rb, 1), b"a");
+        assert_eq!(rb.read_idx(), 1);
+        assert!(!rb.is_full());
+
+        assert_eq!(rb.write(b"e"), 1);
+        assert_eq!(rb.write_idx(), 1);
+        assert!(rb.is_full());
+
+        assert_eq!(read(&mut rb, 2), b"bc");
+        assert_eq!(rb.read_idx(), 0);
+        assert_eq!(read(&mut rb, 5), b"e");
+        assert!(rb.is_empty());
+    }
+
+    #[test]
+    fn test_get() {
+        let mut rb = StaticRingBuffer::<u8, 3>::new();
+        rb.write(b"abc");
+        rb.consume(2);
+        rb.write(b"de");
+        assert_eq!(rb.get(0), Some(&b'c'));
+        assert_eq!(rb.get(2), Some(&b'e'));
+        assert_eq!(rb.get(3), None);
+    }
+
+    #[test]
+    fn test_as_slices_and_peek() {
+        let mut rb = StaticRingBuffer::<u8, 4>::new();
+        rb.write(b"abc");
+        assert_eq!(rb.as_slices(), (&b"abc"[..], &b""[..]));
+
+        rb.consume(2);
+        rb.write(b"def");
+        // Данные переходят через край: "cd" в конце массива, "ef" в начале
+        assert_eq!(rb.as_slices(), (&b"cd"[..], &b"ef"[..]));
+        assert_eq!(rb.peek(1), (&b"c"[..], &b""[..]));
+        assert_eq!(rb.peek(3), (&b"cd"[..], &b"e"[..]));
+        assert_eq!(rb.peek(10), rb.as_slices());
+        assert_eq!(rb.len(), 4);
+    }
+
+    #[test]
+    fn test_consume_and_read_into() {
+        let mut rb = StaticRingBuffer::<u8, 4>::new();
+        rb.write(b"abcd");
+        assert_eq!(rb.consume(3), 3);
+        assert!(!rb.is_full());
+        rb.write(b"efg");
+
+        let mut out = [0; 3];
+        assert_eq!(rb.read_into(&mut out), 3);
+        assert_eq!(&out, b"def");
+        assert_eq!(rb.read_into(&mut out), 1);
+        assert_eq!(out[0], b'g');
+        assert_eq!(rb.consume(5), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read() {
+        let mut rb = StaticRingBuffer::<u8, 3>::new();
+        rb.write(b"abcd");
+        assert_eq!(rb.read(2), b"ab");
+        assert_eq!(rb.read(5), b"c");
+        assert_eq!(rb.read(1), vec![]);
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut rb = StaticRingBuffer::<u8, 3>::with_policy(OverflowPolicy::OverwriteOldest);
+        assert_eq!(rb.write(b"abc"), 3);
+        assert_eq!(rb.write(b"de"), 2);
+        assert_eq!(rb.dropped(), 2);
+        assert_eq!(rb.read_idx(), 2);
+        assert!(rb.is_full());
+        assert_eq!(read(&mut rb, 5), b"cde");
+
+        assert_eq!(rb.write(b"1234567"), 7);
+        assert_eq!(rb.dropped(), 6);
+        assert_eq!(read(&mut rb, 5), b"567");
+    }
+
+    #[test]
+    fn test_push_overwrite_oldest() {
+        let mut rb = StaticRingBuffer::<_, 2>::with_policy(OverflowPolicy::OverwriteOldest);
+        assert_eq!(rb.push(1), Ok(()));
+        assert_eq!(rb.push(2), Ok(()));
+        assert_eq!(rb.push(3), Ok(()));
+        assert_eq!(rb.dropped(), 1);
+        assert_eq!(rb.pop(), Some(2));
+        assert_eq!(rb.pop(), Some(3));
+        assert_eq!(rb.pop(), None);
+    }
+
+    #[test]
+    fn test_reject_does_not_drop() {
+        let mut rb = StaticRingBuffer::<u8, 2>::new();
+        assert_eq!(rb.policy(), OverflowPolicy::Reject);
+        assert_eq!(rb.write(b"abc"), 2);
+        assert_eq!(rb.push(b'd'), Err(b'd'));
+        assert_eq!(rb.dropped(), 0);
+
+        // Расти некуда: Grow ведёт себя как Reject
+        let mut rb = StaticRingBuffer::<u8, 2>::with_policy(OverflowPolicy::Grow { max: 8 });
+        assert_eq!(rb.write(b"abc"), 2);
+        assert_eq!(rb.capacity(), 2);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Write(Vec<u8>),
+        Read(usize),
+        Push(u8),
+        Pop,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            prop::collection::vec(any::<u8>(), 0..8).prop_map(Op::Write),
+            (0..8usize).prop_map(Op::Read),
+            any::<u8>().prop_map(Op::Push),
+            Just(Op::Pop),
+        ]
+    }
+
+    // Ёмкость задаётся на этапе компиляции, поэтому модель проверяется
+    // для нескольких N по очереди
+    fn check_matches_vec_deque<const N: usize>(ops: &[Op]) -> Result<(), TestCaseError> {
+        let mut rb = StaticRingBuffer::<u8, N>::new();
+        let mut model = VecDeque::new();
+
+        for op in ops {
+            match op {
+                Op::Write(buf) => {
+                    let expected = buf.len().min(N - model.len());
+                    model.extend(&buf[..expected]);
+                    prop_assert_eq!(rb.write(buf), expected);
+                }
+                Op::Read(n) => {
+                    let expected: Vec<u8> = model.drain(..(*n).min(model.len())).collect();
+                    prop_assert_eq!(read(&mut rb, *n), expected);
+                }
+                Op::Push(item) => {
+                    if model.len() < N {
+                        model.push_back(*item);
+                        prop_assert_eq!(rb.push(*item), Ok(()));
+                    } else {
+                        prop_assert_eq!(rb.push(*item), Err(*item));
+                    }
+                }
+                Op::Pop => prop_assert_eq!(rb.pop(), model.pop_front()),
+            }
+            prop_assert_eq!(rb.len(), model.len());
+            prop_assert_eq!(rb.is_empty(), model.is_empty());
+            prop_assert_eq!(rb.is_full(), model.len() == N);
+            prop_assert_eq!(rb.available(), N - model.len());
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_vec_deque(ops in prop::collection::vec(op(), 0..64)) {
+            check_matches_vec_deque::<1>(&ops)?;
+            check_matches_vec_deque::<3>(&ops)?;
+            check_matches_vec_deque::<7>(&ops)?;
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// Сообщения поверх байтового буфера: перед каждым сообщением записывается его длина
+// в формате varint (LEB128: по 7 бит на байт, старший бит - "дальше ещё байт").
+//
+// Сообщение записывается целиком или не записывается вовсе, поэтому читатель никогда
+// не видит его часть. Если заголовок не разбирается или обещает больше байт, чем есть
+// в буфере, значит данные испорчены: read_message возвращает CorruptHeader и
+// ничего не извлекает. Найти в испорченных данных начало следующего сообщения нельзя,
+// поэтому читатель может только отбросить их через consume(len()).
+// В режиме OverwriteOldest запись сама вытесняет испорченные данные целиком, иначе
+// буфер навсегда остался бы занят ими.
+
+use crate::{OverflowPolicy, RingBuffer};
+
+// Длина u64 в varint занимает не больше 10 байт
+const MAX_HEADER_LEN: usize = 10;
+
+#[derive(Debug, PartialEq)]
+pub enum FrameError {
+    // Сообщение вместе с заголовком не помещается в буфер
+    NoSpaceLeft { needed: usize, available: usize },
+    CorruptHeader,
+}
+
+impl RingBuffer<u8> {
+    pub fn write_message(&mut self, message: &[u8]) -> Result<(), FrameError> {
+        let header = encode_len(message.len());
+        let needed = header.len() + message.len();
+
+        self.reserve(needed);
+        if self.policy() == OverflowPolicy::OverwriteOldest && needed <= self.capacity() {
+            // Вытесняются только целые сообщения, чтобы в начале буфера не остался обрывок
+            while self.available() < needed {
+                match self.peek_header() {
+                    Ok((header_len, len)) => self.discard(header_len + len),
+                    Err(_) => self.discard(self.len()),
+                }
+            }
+        }
+
+        if self.available() < needed {
+            self.metrics.rejected_writes += 1;
+            return Err(FrameError::NoSpaceLeft {
+                needed,
+                available: self.available(),
+            });
+        }
+
+        self.write_available(&header);
+        self.write_available(message);
+        Ok(())
+    }
+
+    // Извлекает одно сообщение целиком; None - если буфер пуст
+    pub fn read_message(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let (header_len, len) = self.peek_header()?;
+        self.consume(header_len);
+        Ok(Some(self.read(len)))
+    }
+
+    // Длина заголовка и сообщения в начале буфера
+    fn peek_header(&self) -> Result<(usize, usize), FrameError> {
+        let mut len: u64 = 0;
+        for (i, &byte) in (0..MAX_HEADER_LEN).map_while(|i| self.get(i)).enumerate() {
+            let bits = u64::from(byte & 0x7f);
+            if i == MAX_HEADER_LEN - 1 && bits > 1 {
+                // В десятом байте остаётся место только под старший бит u64
+                return Err(FrameError::CorruptHeader);
+            }
+            len |= bits << (7 * i);
+
+            if byte & 0x80 == 0 {
+                let header_len = i + 1;
+                return match usize::try_from(len) {
+                    Ok(len) if len <= self.len() - header_len => Ok((header_len, len)),
+                    _ => Err(FrameError::CorruptHeader),
+                };
+            }
+        }
+
+        // Заголовок оборвался или слишком длинный
+        Err(FrameError::CorruptHeader)
+    }
+}
+
+fn encode_len(len: usize) -> Vec<u8> {
+    let mut len = len as u64;
+    let mut header = Vec::with_capacity(MAX_HEADER_LEN);
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            header.push(byte);
+            return header;
+        }
+        header.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn test_encode_len() {
+        assert_eq!(encode_len(0), [0]);
+        assert_eq!(encode_len(127), [0x7f]);
+        assert_eq!(encode_len(128), [0x80, 0x01]);
+        assert_eq!(encode_len(300), [0xac, 0x02]);
+        assert_eq!(encode_len(usize::MAX).len(), MAX_HEADER_LEN);
+    }
+
+    #[test]
+    fn test_messages_roundtrip() {
+        let mut rb = RingBuffer::new(16);
+        rb.write_message(b"hello").unwrap();
+        rb.write_message(b"").unwrap();
+        rb.write_message(b"ring").unwrap();
+        assert_eq!(rb.len(), 12);
+
+        assert_eq!(rb.read_message(), Ok(Some(b"hello".to_vec())));
+        assert_eq!(rb.read_message(), Ok(Some(Vec::new())));
+        assert_eq!(rb.read_message(), Ok(Some(b"ring".to_vec())));
+        assert_eq!(rb.read_message(), Ok(None));
+    }
+
+    #[test]
+    fn test_long_message_header() {
+        let message = vec![7; 200];
+        let mut rb = RingBuffer::new(300);
+        rb.write_message(&message).unwrap();
+        assert_eq!(rb.len(), 202);
+        assert_eq!(rb.read_message(), Ok(Some(message)));
+    }
+
+    #[test]
+    fn test_all_or_nothing() {
+        let mut rb = RingBuffer::new(8);
+        rb.write_message(b"abcd").unwrap();
+        assert_eq!(
+            rb.write_message(b"efgh"),
+            Err(FrameError::NoSpaceLeft {
+                needed: 5,
+                available: 3
+            })
+        );
+        // Неудачная запись ничего не оставила в буфере
+        assert_eq!(rb.len(), 5);
+        assert_eq!(rb.read_message(), Ok(Some(b"abcd".to_vec())));
+        assert_eq!(rb.read_message(), Ok(None));
+    }
+
+    #[test]
+    fn test_overwrite_evicts_whole_messages() {
+        let mut rb = RingBuffer::with_policy(10, OverflowPolicy::OverwriteOldest);
+        rb.write_message(b"abc").unwrap();
+        rb.write_message(b"de").unwrap();
+        rb.write_message(b"fgh").unwrap();
+        // "abc" вытеснено целиком вместе с заголовком
+        assert_eq!(rb.dropped(), 4);
+        assert_eq!(rb.read_message(), Ok(Some(b"de".to_vec())));
+        assert_eq!(rb.read_message(), Ok(Some(b"fgh".to_vec())));
+
+        assert!(matches!(
+            rb.write_message(&[0; 10]),
+            Err(FrameError::NoSpaceLeft { .. })
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_header() {
+        // Заголовок обещает 5 байт, а в буфере только 2
+        let mut rb = RingBuffer::new(8);
+        rb.write(&[5, b'a', b'b']);
+        assert_eq!(rb.read_message(), Err(FrameError::CorruptHeader));
+        assert_eq!(rb.len(), 3);
+
+        // Заголовок оборвался: у последнего байта стоит бит продолжения
+        let mut rb = RingBuffer::new(8);
+        rb.write(&[0x80, 0x80]);
+        assert_eq!(rb.read_message(), Err(FrameError::CorruptHeader));
+
+        // Заголовок длиннее 10 байт
+        let mut rb = RingBuffer::new(16);
+        rb.write(&[0xff; 11]);
+        assert_eq!(rb.read_message(), Err(FrameError::CorruptHeader));
+    }
+
+    #[test]
+    fn test_overwrite_discards_corrupt_data() {
+        let mut rb = RingBuffer::with_policy(8, OverflowPolicy::OverwriteOldest);
+        rb.write(&[5, b'a', b'b']);
+        assert_eq!(rb.read_message(), Err(FrameError::CorruptHeader));
+
+        // Места мало, а вытеснить по сообщениям нельзя: всё испорченное отбрасывается
+        rb.write_message(b"cdefg").unwrap();
+        assert_eq!(rb.dropped(), 3);
+        assert_eq!(rb.read_message(), Ok(Some(b"cdefg".to_vec())));
+
+        // Читатель без перезаписи пропускает испорченные данные сам
+        let mut rb = RingBuffer::new(8);
+        rb.write(&[0x80, 0x80]);
+        assert_eq!(rb.read_message(), Err(FrameError::CorruptHeader));
+        rb.consume(rb.len());
+        assert_eq!(rb.read_message(), Ok(None));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_messages_match_vec_deque(
+            size in 1..64usize,
+            ops in prop::collection::vec(prop::option::of(prop::collection::vec(any::<u8>(), 0..20)), 0..64),
+        ) {
+            // Some(сообщение) - запись, None - чтение
+            let mut rb = RingBuffer::new(size);
+            let mut model = VecDeque::new();
+
+            for op in ops {
+                match op {
+                    Some(message) => {
+                        let needed = encode_len(message.len()).len() + message.len();
+                        let fits = needed <= rb.available();
+                        prop_assert_eq!(rb.write_message(&message).is_ok(), fits);
+                        if fits {
+                            model.push_back(message);
+                        }
+                    }
+                    None => prop_assert_eq!(rb.read_message(), Ok(model.pop_front())),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Байтовый буфер как источник и приёмник для std::io: io::copy, BufReader, write!.
+//
+// Однопоточный буфер не может ждать, поэтому он всегда работает в неблокирующем
+// режиме: запись в полный буфер и чтение из пустого возвращают ErrorKind::WouldBlock.
+
+use std::io;
+
+use crate::RingBuffer;
+
+impl io::Write for RingBuffer<u8> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match RingBuffer::write(self, buf) {
+            0 if !buf.is_empty() => Err(io::ErrorKind::WouldBlock.into()),
+            written => Ok(written),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Read for RingBuffer<u8> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.is_empty() && !out.is_empty() {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+
+        Ok(self.read_into(out))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Cursor, Read, Write};
+
+    #[test]
+    fn test_write_macro_and_read_exact() {
+        let mut rb = RingBuffer::new(16);
+        let name = "ab";
+        write!(rb, "{}-{}", 12, name).unwrap();
+
+        let mut out = [0; 5];
+        rb.read_exact(&mut out).unwrap();
+        assert_eq!(&out, b"12-ab");
+    }
+
+    #[test]
+    fn test_would_block() {
+        // Собственные методы write/read буфера скрывают методы трейтов,
+        // поэтому трейты вызываются явно
+        let mut rb = RingBuffer::new(2);
+        assert_eq!(Write::write(&mut rb, b"abc").unwrap(), 2);
+        let err = Write::write(&mut rb, b"c").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        let err = rb.write_all(b"abc").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        let mut out = [0; 4];
+        assert_eq!(Read::read(&mut rb, &mut out).unwrap(), 2);
+        let err = Read::read(&mut rb, &mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        // Пустой вызов не ждёт и не считается ошибкой
+        assert_eq!(Read::read(&mut rb, &mut []).unwrap(), 0);
+        assert_eq!(Write::write(&mut rb, b"").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_copy_and_buf_reader() {
+        let mut rb = RingBuffer::new(32);
+        let copied = io::copy(&mut Cursor::new("first\nsecond\n"), &mut rb).unwrap();
+        assert_eq!(copied, 13);
+
+        let mut reader = BufReader::new(&mut rb);
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "first\n");
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "second\n");
+    }
+}
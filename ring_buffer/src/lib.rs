@@ -0,0 +1,684 @@
+// Общий кольцевой буфер для домашних работ hw05, hw07 и hw13.
+//
+// Элементы хранятся в векторе, размер которого меняется только через resize
+// или политику Grow. Свободные ячейки заполнены значением T::default(),
+// поэтому тип элемента должен реализовывать Default.
+//
+// Всё, кроме StaticRingBuffer, требует фичи std (она включена по умолчанию).
+// Без неё крейт собирается как no_std. PersistentRingBuffer есть только на unix:
+// он читает и пишет файл через pread/pwrite из std::os::unix.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "std")]
+pub mod broadcast;
+mod fixed;
+#[cfg(feature = "std")]
+mod frame;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
+pub mod mpmc;
+#[cfg(feature = "std")]
+mod padded;
+#[cfg(all(feature = "std", unix))]
+mod persistent;
+#[cfg(feature = "std")]
+pub mod spsc;
+#[cfg(feature = "std")]
+mod text;
+
+pub use fixed::StaticRingBuffer;
+#[cfg(feature = "std")]
+pub use frame::FrameError;
+#[cfg(feature = "std")]
+pub use metrics::{Metrics, MetricsFormat, MetricsReporter};
+#[cfg(all(feature = "std", unix))]
+pub use persistent::{PersistentRingBuffer, StoreError};
+#[cfg(feature = "std")]
+pub use text::{TextRingBuffer, Written};
+
+// Что делать при записи в заполненный буфер
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OverflowPolicy {
+    // Отказать в записи того, что не поместилось
+    #[default]
+    Reject,
+    // Вытеснить самые старые элементы; их количество копится в dropped()
+    OverwriteOldest,
+    // Удваивать ёмкость, пока она не достигнет max, а дальше - как Reject
+    Grow {
+        max: usize,
+    },
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq)]
+pub enum ResizeError {
+    // Новая ёмкость меньше количества непрочитанных элементов
+    TooSmall { len: usize, capacity: usize },
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct RingBuffer<T> {
+    read_idx: usize,
+    write_idx: usize,
+    is_full: bool,
+    data: Vec<T>,
+    policy: OverflowPolicy,
+    pub(crate) dropped: u64,
+    pub(crate) metrics: Metrics,
+}
+
+#[cfg(feature = "std")]
+impl<T: Default> RingBuffer<T> {
+    pub fn new(size: usize) -> Self {
+        Self::with_policy(size, OverflowPolicy::default())
+    }
+
+    pub fn with_policy(size: usize, policy: OverflowPolicy) -> Self {
+        RingBuffer {
+            read_idx: 0,
+            write_idx: 0,
+            is_full: false,
+            data: (0..size).map(|_| T::default()).collect(),
+            policy,
+            dropped: 0,
+            metrics: Metrics::default(),
+        }
+    }
+
+    // Добавляет элемент в конец очереди; если места нет, возвращает его обратно.
+    // В режиме перезаписи вместо этого вытесняется самый старый элемент.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        self.reserve(1);
+        if self.available() == 0 {
+            if self.policy != OverflowPolicy::OverwriteOldest || self.capacity() == 0 {
+                self.metrics.rejected_writes += 1;
+                return Err(item);
+            }
+            self.discard(1);
+        }
+
+        self.data[self.write_idx] = item;
+        self.write_idx = (self.write_idx + 1) % self.capacity();
+        self.is_full = self.write_idx == self.read_idx;
+
+        self.metrics.items_in += 1;
+        self.metrics.record_len(self.len());
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            self.metrics.empty_reads += 1;
+            return None;
+        }
+
+        let item = std::mem::take(&mut self.data[self.read_idx]);
+        self.read_idx = (self.read_idx + 1) % self.capacity();
+        self.is_full = false;
+
+        self.metrics.items_out += 1;
+        Some(item)
+    }
+
+    // Меняет ёмкость, перекладывая непрочитанные элементы в начало нового вектора
+    // в порядке чтения. Ёмкость не может стать меньше количества элементов.
+    pub fn resize(&mut self, capacity: usize) -> Result<(), ResizeError> {
+        let len = self.len();
+        if capacity < len {
+            return Err(ResizeError::TooSmall { len, capacity });
+        }
+
+        let mut data = Vec::with_capacity(capacity);
+        for i in 0..len {
+            let idx = (self.read_idx + i) % self.capacity();
+            data.push(std::mem::take(&mut self.data[idx]));
+        }
+        data.resize_with(capacity, T::default);
+
+        self.data = data;
+        self.read_idx = 0;
+        self.write_idx = if capacity == 0 { 0 } else { len % capacity };
+        self.is_full = capacity > 0 && len == capacity;
+
+        Ok(())
+    }
+
+    // Уменьшает ёмкость до количества непрочитанных элементов
+    pub fn shrink_to_fit(&mut self) {
+        self.resize(self.len())
+            .expect("ёмкость не меньше количества элементов");
+    }
+
+    // В режиме Grow удваивает ёмкость (не больше max), пока не освободится
+    // место под needed элементов или пока не будет достигнут max
+    pub(crate) fn reserve(&mut self, needed: usize) {
+        let OverflowPolicy::Grow { max } = self.policy else {
+            return;
+        };
+        if self.available() >= needed || self.capacity() >= max {
+            return;
+        }
+
+        let mut capacity = std::cmp::max(self.capacity(), 1);
+        while capacity - self.len() < needed && capacity < max {
+            capacity = std::cmp::min(capacity * 2, max);
+        }
+        self.resize(capacity).expect("ёмкость только растёт");
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Copy + Default> RingBuffer<T> {
+    // Записывает столько элементов из buf, сколько помещается, и возвращает их количество.
+    // В режиме перезаписи buf принимается целиком: старые элементы вытесняются,
+    // а если buf длиннее буфера, в нём остаются только последние элементы.
+    pub fn write(&mut self, buf: &[T]) -> usize {
+        if self.policy == OverflowPolicy::OverwriteOldest && self.capacity() > 0 {
+            let skipped = buf.len().saturating_sub(self.capacity());
+            let overflow = (buf.len() - skipped).saturating_sub(self.available());
+            self.discard(overflow);
+            self.dropped += skipped as u64;
+            self.write_available(&buf[skipped..]);
+            return buf.len();
+        }
+
+        self.reserve(buf.len());
+        let count = self.write_available(buf);
+        if count < buf.len() {
+            self.metrics.rejected_writes += 1;
+        }
+        count
+    }
+
+    fn write_available(&mut self, buf: &[T]) -> usize {
+        let count = std::cmp::min(buf.len(), self.available());
+        if count == 0 {
+            return 0;
+        }
+
+        // Запись идёт максимум двумя кусками: до конца вектора и с его начала
+        let first = std::cmp::min(count, self.capacity() - self.write_idx);
+        self.data[self.write_idx..self.write_idx + first].copy_from_slice(&buf[..first]);
+        self.data[..count - first].copy_from_slice(&buf[first..count]);
+
+        self.write_idx = (self.write_idx + count) % self.capacity();
+        self.is_full = self.write_idx == self.read_idx;
+
+        self.metrics.items_in += count as u64;
+        self.metrics.record_len(self.len());
+        count
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Copy> RingBuffer<T> {
+    // Читает не больше n элементов: если в буфере меньше, возвращается только то, что есть
+    pub fn read(&mut self, n: usize) -> Vec<T> {
+        self.record_empty_read(n);
+        let (first, second) = self.peek(n);
+        let result = [first, second].concat();
+        self.consume(result.len());
+        result
+    }
+
+    // Копирует элементы в out без выделения памяти и возвращает их количество
+    pub fn read_into(&mut self, out: &mut [T]) -> usize {
+        self.record_empty_read(out.len());
+        let (first, second) = self.peek(out.len());
+        out[..first.len()].copy_from_slice(first);
+        out[first.len()..first.len() + second.len()].copy_from_slice(second);
+
+        let count = first.len() + second.len();
+        self.consume(count);
+        count
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> RingBuffer<T> {
+    // Количество элементов, записанных в буфер и ещё не прочитанных
+    pub fn len(&self) -> usize {
+        if self.is_full {
+            self.capacity()
+        } else if self.write_idx >= self.read_idx {
+            self.write_idx - self.read_idx
+        } else {
+            self.write_idx + self.capacity() - self.read_idx
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Элемент на позиции i от начала очереди без извлечения
+    pub fn get(&self, i: usize) -> Option<&T> {
+        if i >= self.len() {
+            return None;
+        }
+
+        Some(&self.data[(self.read_idx + i) % self.capacity()])
+    }
+
+    // Непрочитанные элементы в виде двух непрерывных кусков: до конца вектора
+    // и с его начала. Второй кусок пуст, если данные не переходят через край.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let len = self.len();
+        let first = std::cmp::min(len, self.capacity() - self.read_idx);
+        (
+            &self.data[self.read_idx..self.read_idx + first],
+            &self.data[..len - first],
+        )
+    }
+
+    // Первые n элементов (или меньше, если столько нет) без извлечения и копирования
+    pub fn peek(&self, n: usize) -> (&[T], &[T]) {
+        let (first, second) = self.as_slices();
+        if n <= first.len() {
+            (&first[..n], &[])
+        } else {
+            (
+                first,
+                &second[..std::cmp::min(n - first.len(), second.len())],
+            )
+        }
+    }
+
+    // Пропускает не больше n элементов без копирования и возвращает их количество.
+    // Пропущенные элементы остаются в векторе, пока их не перезапишут.
+    pub fn consume(&mut self, n: usize) -> usize {
+        self.record_empty_read(n);
+        let count = self.advance(n);
+        self.metrics.items_out += count as u64;
+        count
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.is_full
+    }
+
+    // Сколько элементов ещё можно записать
+    pub fn available(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    // Сколько элементов было потеряно из-за перезаписи
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    // Снимок счётчиков использования буфера
+    pub fn metrics(&self) -> Metrics {
+        self.metrics
+    }
+
+    // Отбрасывает n самых старых элементов, засчитывая их как потерянные
+    pub(crate) fn discard(&mut self, n: usize) {
+        self.dropped += self.advance(n) as u64;
+    }
+
+    fn record_empty_read(&mut self, n: usize) {
+        if n > 0 && self.is_empty() {
+            self.metrics.empty_reads += 1;
+        }
+    }
+
+    fn advance(&mut self, n: usize) -> usize {
+        let count = std::cmp::min(n, self.len());
+        if count == 0 {
+            return 0;
+        }
+
+        self.read_idx = (self.read_idx + count) % self.capacity();
+        self.is_full = false;
+        count
+    }
+
+    pub fn read_idx(&self) -> usize {
+        self.read_idx
+    }
+
+    pub fn write_idx(&self) -> usize {
+        self.write_idx
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::VecDeque;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[test]
+    fn test_create_buffer() {
+        let rb: RingBuffer<u8> = RingBuffer::new(3);
+        assert_eq!(rb.capacity(), 3);
+        assert!(rb.is_empty());
+        assert!(!rb.is_full());
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let mut rb = RingBuffer::new(2);
+        assert_eq!(rb.push(String::from("a")), Ok(()));
+        assert_eq!(rb.push(String::from("b")), Ok(()));
+        assert_eq!(rb.push(String::from("c")), Err(String::from("c")));
+        assert!(rb.is_full());
+
+        assert_eq!(rb.pop(), Some(String::from("a")));
+        assert_eq!(rb.push(String::from("d")), Ok(()));
+        assert_eq!(rb.pop(), Some(String::from("b")));
+        assert_eq!(rb.pop(), Some(String::from("d")));
+        assert_eq!(rb.pop(), None);
+    }
+
+    #[test]
+    fn test_structs() {
+        let mut rb = RingBuffer::new(3);
+        let points = [Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+        assert_eq!(rb.write(&points), 2);
+        assert_eq!(rb.pop(), Some(points[0]));
+        assert_eq!(rb.read(5), vec![points[1]]);
+    }
+
+    #[test]
+    fn test_rw_wrap_around() {
+        let mut rb = RingBuffer::new(3);
+
+        assert_eq!(rb.write(b"ab"), 2);
+        assert_eq!(rb.write_idx(), 2);
+
+        assert_eq!(rb.write(b"cd"), 1);
+        assert_eq!(rb.write_idx(), 0);
+        assert!(rb.is_full());
+
+        assert_eq!(rb.read(1), b"a");
+        assert_eq!(rb.read_idx(), 1);
+        assert!(!rb.is_full());
+
+        assert_eq!(rb.write(b"e"), 1);
+        assert_eq!(rb.write_idx(), 1);
+        assert!(rb.is_full());
+
+        assert_eq!(rb.read(2), b"bc");
+        assert_eq!(rb.read_idx(), 0);
+        assert_eq!(rb.read(5), b"e");
+        assert!(rb.is_empty());
+    }
+
+    #[test]
+    fn test_get() {
+        let mut rb = RingBuffer::new(3);
+        rb.write(b"abc");
+        rb.read(2);
+        rb.write(b"de");
+        assert_eq!(rb.get(0), Some(&b'c'));
+        assert_eq!(rb.get(2), Some(&b'e'));
+        assert_eq!(rb.get(3), None);
+    }
+
+    #[test]
+    fn test_as_slices_and_peek() {
+        let mut rb = RingBuffer::new(4);
+        rb.write(b"abc");
+        assert_eq!(rb.as_slices(), (&b"abc"[..], &b""[..]));
+
+        rb.read(2);
+        rb.write(b"def");
+        // Данные переходят через край: "cd" в конце вектора, "ef" в начале
+        assert_eq!(rb.as_slices(), (&b"cd"[..], &b"ef"[..]));
+        assert_eq!(rb.peek(1), (&b"c"[..], &b""[..]));
+        assert_eq!(rb.peek(3), (&b"cd"[..], &b"e"[..]));
+        assert_eq!(rb.peek(10), rb.as_slices());
+        assert_eq!(rb.len(), 4);
+    }
+
+    #[test]
+    fn test_grow_doubles_up_to_max() {
+        let mut rb = RingBuffer::with_policy(2, OverflowPolicy::Grow { max: 10 });
+        assert_eq!(rb.write(b"ab"), 2);
+        rb.read(1);
+        // Данные переходят через край, рост раскладывает их по порядку
+        assert_eq!(rb.write(b"cd"), 2);
+        assert_eq!(rb.capacity(), 4);
+        assert_eq!(rb.as_slices(), (&b"bcd"[..], &b""[..]));
+
+        assert_eq!(rb.write(b"efghijkl"), 7);
+        assert_eq!(rb.capacity(), 10);
+        assert!(rb.is_full());
+        assert_eq!(rb.push(b'x'), Err(b'x'));
+        assert_eq!(rb.read(20), b"bcdefghijk");
+    }
+
+    #[test]
+    fn test_grow_from_zero() {
+        let mut rb = RingBuffer::with_policy(0, OverflowPolicy::Grow { max: 4 });
+        assert_eq!(rb.push(String::from("a")), Ok(()));
+        assert_eq!(rb.capacity(), 1);
+        assert_eq!(rb.push(String::from("b")), Ok(()));
+        assert_eq!(rb.capacity(), 2);
+        assert_eq!(rb.pop(), Some(String::from("a")));
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut rb = RingBuffer::new(4);
+        rb.write(b"abcd");
+        rb.read(3);
+        rb.write(b"ef");
+        assert_eq!(
+            rb.resize(2),
+            Err(ResizeError::TooSmall {
+                len: 3,
+                capacity: 2
+            })
+        );
+
+        assert_eq!(rb.resize(6), Ok(()));
+        assert_eq!(rb.read_idx(), 0);
+        assert_eq!(rb.write_idx(), 3);
+        assert_eq!(rb.write(b"ghij"), 3);
+        assert_eq!(rb.read(10), b"defghi");
+
+        rb.write(b"xy");
+        rb.shrink_to_fit();
+        assert_eq!(rb.capacity(), 2);
+        assert!(rb.is_full());
+        assert_eq!(rb.read(5), b"xy");
+        rb.shrink_to_fit();
+        assert_eq!(rb.capacity(), 0);
+        assert!(!rb.is_full());
+    }
+
+    #[test]
+    fn test_consume_and_read_into() {
+        let mut rb = RingBuffer::new(4);
+        rb.write(b"abcd");
+        assert_eq!(rb.consume(3), 3);
+        assert!(!rb.is_full());
+        rb.write(b"efg");
+
+        let mut out = [0; 3];
+        assert_eq!(rb.read_into(&mut out), 3);
+        assert_eq!(&out, b"def");
+        assert_eq!(rb.read_into(&mut out), 1);
+        assert_eq!(out[0], b'g');
+        assert_eq!(rb.consume(5), 0);
+        assert_eq!(RingBuffer::<u8>::new(0).as_slices(), (&[][..], &[][..]));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut rb = RingBuffer::new(4);
+        rb.write(b"abc");
+        rb.read(2);
+        rb.write(b"defg");
+        rb.read(10);
+        rb.read(1);
+        assert_eq!(rb.pop(), None);
+        assert_eq!(rb.push(1), Ok(()));
+
+        assert_eq!(
+            rb.metrics(),
+            Metrics {
+                high_water_mark: 4,
+                items_in: 7,
+                items_out: 6,
+                rejected_writes: 1,
+                empty_reads: 2,
+                blocked_reads: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut rb = RingBuffer::with_policy(3, OverflowPolicy::OverwriteOldest);
+        assert_eq!(rb.write(b"abc"), 3);
+        assert_eq!(rb.write(b"de"), 2);
+        assert_eq!(rb.dropped(), 2);
+        assert_eq!(rb.read_idx(), 2);
+        assert!(rb.is_full());
+        assert_eq!(rb.read(5), b"cde");
+
+        assert_eq!(rb.write(b"1234567"), 7);
+        assert_eq!(rb.dropped(), 6);
+        assert_eq!(rb.read(5), b"567");
+    }
+
+    #[test]
+    fn test_push_overwrite_oldest() {
+        let mut rb = RingBuffer::with_policy(2, OverflowPolicy::OverwriteOldest);
+        assert_eq!(rb.push(1), Ok(()));
+        assert_eq!(rb.push(2), Ok(()));
+        assert_eq!(rb.push(3), Ok(()));
+        assert_eq!(rb.dropped(), 1);
+        assert_eq!(rb.pop(), Some(2));
+        assert_eq!(rb.pop(), Some(3));
+        assert_eq!(rb.pop(), None);
+    }
+
+    #[test]
+    fn test_reject_does_not_drop() {
+        let mut rb = RingBuffer::new(2);
+        assert_eq!(rb.policy(), OverflowPolicy::Reject);
+        assert_eq!(rb.write(b"abc"), 2);
+        assert_eq!(rb.push(b'd'), Err(b'd'));
+        assert_eq!(rb.dropped(), 0);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut rb: RingBuffer<u8> = RingBuffer::new(0);
+        assert_eq!(rb.write(b"a"), 0);
+        assert_eq!(rb.push(1), Err(1));
+        assert_eq!(rb.read(1), vec![]);
+        assert_eq!(rb.pop(), None);
+        assert!(rb.is_empty());
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Write(Vec<u8>),
+        Read(usize),
+        Push(u8),
+        Pop,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            prop::collection::vec(any::<u8>(), 0..8).prop_map(Op::Write),
+            (0..8usize).prop_map(Op::Read),
+            any::<u8>().prop_map(Op::Push),
+            Just(Op::Pop),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_vec_deque(size in 1..8usize, ops in prop::collection::vec(op(), 0..64)) {
+            let mut rb = RingBuffer::new(size);
+            let mut model = VecDeque::new();
+
+            for op in ops {
+                match op {
+                    Op::Write(buf) => {
+                        let expected = buf.len().min(size - model.len());
+                        model.extend(&buf[..expected]);
+                        prop_assert_eq!(rb.write(&buf), expected);
+                    }
+                    Op::Read(n) => {
+                        let expected: Vec<u8> = model.drain(..n.min(model.len())).collect();
+                        prop_assert_eq!(rb.read(n), expected);
+                    }
+                    Op::Push(item) => {
+                        if model.len() < size {
+                            model.push_back(item);
+                            prop_assert_eq!(rb.push(item), Ok(()));
+                        } else {
+                            prop_assert_eq!(rb.push(item), Err(item));
+                        }
+                    }
+                    Op::Pop => prop_assert_eq!(rb.pop(), model.pop_front()),
+                }
+                prop_assert_eq!(rb.len(), model.len());
+                prop_assert_eq!(rb.is_empty(), model.is_empty());
+                prop_assert_eq!(rb.is_full(), model.len() == size);
+                prop_assert_eq!(rb.available(), size - model.len());
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_overwrite_keeps_tail(size in 1..8usize, ops in prop::collection::vec(op(), 0..64)) {
+            let mut rb = RingBuffer::with_policy(size, OverflowPolicy::OverwriteOldest);
+            let mut model = VecDeque::new();
+            let mut dropped = 0u64;
+
+            for op in ops {
+                match op {
+                    Op::Write(buf) => {
+                        model.extend(&buf);
+                        prop_assert_eq!(rb.write(&buf), buf.len());
+                    }
+                    Op::Read(n) => {
+                        let expected: Vec<u8> = model.drain(..n.min(model.len())).collect();
+                        prop_assert_eq!(rb.read(n), expected);
+                    }
+                    Op::Push(item) => {
+                        model.push_back(item);
+                        prop_assert_eq!(rb.push(item), Ok(()));
+                    }
+                    Op::Pop => prop_assert_eq!(rb.pop(), model.pop_front()),
+                }
+                while model.len() > size {
+                    model.pop_front();
+                    dropped += 1;
+                }
+                prop_assert_eq!(rb.len(), model.len());
+                prop_assert_eq!(rb.dropped(), dropped);
+            }
+        }
+    }
+}
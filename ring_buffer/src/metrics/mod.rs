@@ -0,0 +1,174 @@
+// Счётчики использования буфера: насколько близко он подходил к заполнению,
+// сколько через него прошло данных и сколько раз запись или чтение не удались.
+//
+// Снимок Metrics можно вывести строкой или в JSON, а MetricsReporter делает это
+// периодически в отдельном потоке.
+
+use std::io::Write;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// Объём считается в элементах буфера: для RingBuffer<T> это значения T,
+// для байтовых и текстовых буферов - байты
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    // Наибольшее количество непрочитанных элементов за всё время
+    pub high_water_mark: usize,
+    // Записанные и прочитанные элементы
+    pub items_in: u64,
+    pub items_out: u64,
+    // Записи, которые не поместились целиком (NoSpaceLeft)
+    pub rejected_writes: u64,
+    // Чтения из пустого буфера
+    pub empty_reads: u64,
+    // Чтения, которым пришлось ждать данных (только у буферов с блокирующим чтением)
+    pub blocked_reads: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum MetricsFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Metrics {
+    pub fn render(&self, format: MetricsFormat) -> String {
+        let fields = [
+            ("high_water_mark", self.high_water_mark as u64),
+            ("items_in", self.items_in),
+            ("items_out", self.items_out),
+            ("rejected_writes", self.rejected_writes),
+            ("empty_reads", self.empty_reads),
+            ("blocked_reads", self.blocked_reads),
+        ];
+
+        match format {
+            MetricsFormat::Text => fields
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join(" "),
+            MetricsFormat::Json => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("\"{}\":{}", name, value))
+                    .collect();
+                format!("{{{}}}", fields.join(","))
+            }
+        }
+    }
+
+    pub(crate) fn record_len(&mut self, len: usize) {
+        self.high_water_mark = std::cmp::max(self.high_water_mark, len);
+    }
+}
+
+// Поток, который раз в interval пишет снимок метрик строкой в sink.
+// Останавливается при удалении или при ошибке записи.
+pub struct MetricsReporter {
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MetricsReporter {
+    pub fn spawn<F, W>(interval: Duration, format: MetricsFormat, source: F, mut sink: W) -> Self
+    where
+        F: Fn() -> Metrics + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                if writeln!(sink, "{}", source().render(format)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for MetricsReporter {
+    fn drop(&mut self) {
+        // Закрытый канал будит поток сразу, не дожидаясь конца интервала
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct SharedSink(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn sample() -> Metrics {
+        Metrics {
+            high_water_mark: 3,
+            items_in: 10,
+            items_out: 7,
+            rejected_writes: 1,
+            empty_reads: 2,
+            blocked_reads: 0,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            sample().render(MetricsFormat::Text),
+            "high_water_mark=3 items_in=10 items_out=7 rejected_writes=1 empty_reads=2 blocked_reads=0"
+        );
+        assert_eq!(
+            sample().render(MetricsFormat::Json),
+            r#"{"high_water_mark":3,"items_in":10,"items_out":7,"rejected_writes":1,"empty_reads":2,"blocked_reads":0}"#
+        );
+    }
+
+    #[test]
+    fn test_reporter() {
+        let sink = SharedSink::default();
+        let reporter = MetricsReporter::spawn(
+            Duration::from_millis(5),
+            MetricsFormat::Json,
+            sample,
+            sink.clone(),
+        );
+        while sink
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count()
+            < 2
+        {
+            thread::sleep(Duration::from_millis(1));
+        }
+        drop(reporter);
+
+        let output = String::from_utf8(sink.0.lock().unwrap().clone()).unwrap();
+        let line = sample().render(MetricsFormat::Json);
+        assert!(output.lines().all(|l| l == line));
+    }
+}
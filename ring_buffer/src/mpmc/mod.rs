@@ -0,0 +1,301 @@
+// Ограниченная очередь без блокировок для нескольких писателей и читателей (MPMC)
+// по схеме Дмитрия Вьюкова.
+//
+// У каждой ячейки есть счётчик sequence. Писатель с позицией pos может занять ячейку,
+// только если sequence == pos; после записи он ставит sequence = pos + 1, и ячейку
+// может забрать читатель с той же позицией. Читатель после чтения ставит
+// sequence = pos + capacity, открывая ячейку писателю следующего круга.
+// Позиции писателей и читателей раздаются через compare_exchange, поэтому
+// потоки конкурируют только за счётчик, а не за весь буфер.
+//
+// Позиции растут с переполнением по модулю usize, поэтому ёмкость округляется вверх
+// до степени двойки и ячейка вычисляется как pos & mask: степень двойки делит
+// 2^usize::BITS, и после переполнения позиция попадает в ту же ячейку, что и до него.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::padded::CachePadded;
+
+struct Slot<T> {
+    sequence: AtomicUsize,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+pub struct Queue<T> {
+    write_pos: CachePadded<AtomicUsize>,
+    read_pos: CachePadded<AtomicUsize>,
+    // Ёмкость минус один
+    mask: usize,
+    slots: Box<[Slot<T>]>,
+}
+
+// Ячейкой в каждый момент владеет ровно один поток: тот, кто выиграл её позицию
+unsafe impl<T: Send> Send for Queue<T> {}
+unsafe impl<T: Send> Sync for Queue<T> {}
+
+impl<T> Queue<T> {
+    // Ёмкость округляется вверх до степени двойки, но не меньше 2: при одной ячейке
+    // метки "свободна для следующего круга" и "заполнена" совпадают.
+    pub fn new(capacity: usize) -> Self {
+        Self::starting_at(capacity, 0)
+    }
+
+    // Позиции начинаются со start; тесты так проверяют переполнение позиций
+    fn starting_at(capacity: usize, start: usize) -> Self {
+        let capacity = capacity.max(2).next_power_of_two();
+        let mask = capacity - 1;
+
+        // Ячейка ждёт писателя с первой позицией, которая в неё попадает
+        let mut slots: Box<[Slot<T>]> = (0..capacity)
+            .map(|_| Slot {
+                sequence: AtomicUsize::new(0),
+                data: UnsafeCell::new(MaybeUninit::uninit()),
+            })
+            .collect();
+        for i in 0..capacity {
+            let pos = start.wrapping_add(i);
+            *slots[pos & mask].sequence.get_mut() = pos;
+        }
+
+        Self {
+            write_pos: CachePadded(AtomicUsize::new(start)),
+            read_pos: CachePadded(AtomicUsize::new(start)),
+            mask,
+            slots,
+        }
+    }
+
+    // Добавляет элемент в конец очереди; если места нет, возвращает его обратно
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let mut pos = self.write_pos.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[pos & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let diff = sequence.wrapping_sub(pos) as isize;
+
+            if diff == 0 {
+                match self.write_pos.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        unsafe { (*slot.data.get()).write(item) };
+                        slot.sequence.store(pos.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                // Ячейку ещё не освободил читатель прошлого круга
+                return Err(item);
+            } else {
+                pos = self.write_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let mut pos = self.read_pos.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[pos & self.mask];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let diff = sequence.wrapping_sub(pos.wrapping_add(1)) as isize;
+
+            if diff == 0 {
+                match self.read_pos.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let item = unsafe { (*slot.data.get()).assume_init_read() };
+                        slot.sequence
+                            .store(pos.wrapping_add(self.capacity()), Ordering::Release);
+                        return Some(item);
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                // Писатель ещё не заполнил ячейку
+                return None;
+            } else {
+                pos = self.read_pos.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    // Приблизительное количество элементов: пока другие потоки пишут и читают,
+    // значение может устареть сразу после возврата. Позиция чтения загружается первой,
+    // поэтому разность не бывает отрицательной, но может превысить ёмкость.
+    pub fn len(&self) -> usize {
+        let read = self.read_pos.load(Ordering::Acquire);
+        let write = self.write_pos.load(Ordering::Acquire);
+        std::cmp::min(write.wrapping_sub(read), self.capacity())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn available(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+impl<T: Copy> Queue<T> {
+    // Записывает элементы по одному, пока есть место, и возвращает их количество.
+    // Элементы разных писателей могут перемежаться.
+    pub fn write(&self, buf: &[T]) -> usize {
+        buf.iter()
+            .take_while(|&&item| self.push(item).is_ok())
+            .count()
+    }
+
+    // Читает не больше n элементов: если в очереди меньше, возвращается только то, что есть
+    pub fn read(&self, n: usize) -> Vec<T> {
+        (0..n).map_while(|_| self.pop()).collect()
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_push_pop() {
+        let queue = Queue::new(2);
+        assert_eq!(queue.push(String::from("a")), Ok(()));
+        assert_eq!(queue.push(String::from("b")), Ok(()));
+        assert_eq!(queue.push(String::from("c")), Err(String::from("c")));
+        assert_eq!(queue.pop(), Some(String::from("a")));
+        assert_eq!(queue.push(String::from("c")), Ok(()));
+        assert_eq!(queue.pop(), Some(String::from("b")));
+        assert_eq!(queue.pop(), Some(String::from("c")));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_write_read() {
+        let queue = Queue::new(4);
+        assert_eq!(queue.write(b"abc"), 3);
+        assert_eq!(queue.read(2), b"ab");
+        assert_eq!(queue.write(b"defg"), 3);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.available(), 0);
+        assert_eq!(queue.read(10), b"cdef");
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_rounds_up() {
+        let queue = Queue::new(3);
+        assert_eq!(queue.capacity(), 4);
+        assert_eq!(queue.write(b"abcde"), 4);
+    }
+
+    #[test]
+    fn test_positions_overflow() {
+        let queue = Queue::starting_at(4, usize::MAX - 5);
+        for round in 0..4u8 {
+            let buf = [round, round + 10, round + 20];
+            assert_eq!(queue.write(&buf), 3);
+            assert_eq!(queue.len(), 3);
+            assert_eq!(queue.read(2), buf[..2]);
+            assert_eq!(queue.pop(), Some(buf[2]));
+            assert!(queue.is_empty());
+        }
+        assert_eq!(queue.write(b"abcde"), 4);
+        assert_eq!(queue.read(10), b"abcd");
+    }
+
+    #[test]
+    fn test_small_capacity() {
+        for capacity in [0, 1] {
+            let queue = Queue::new(capacity);
+            assert_eq!(queue.capacity(), 2);
+            assert_eq!(queue.write(b"abc"), 2);
+            assert_eq!(queue.read(3), b"ab");
+        }
+    }
+
+    #[test]
+    fn test_drops_remaining_items() {
+        let item = Arc::new(());
+        let queue = Queue::new(3);
+        for _ in 0..3 {
+            queue.push(item.clone()).unwrap();
+        }
+        queue.pop();
+        assert_eq!(Arc::strong_count(&item), 3);
+
+        drop(queue);
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn test_multithread_write_read() {
+        let queue = Queue::new(100);
+        let num_threads = 10;
+        let items_per_thread = 10_000;
+
+        let counts = thread::scope(|s| {
+            for i in 0..num_threads {
+                let queue = &queue;
+                s.spawn(move || {
+                    for k in 0..items_per_thread {
+                        // Каждый элемент уникален: (номер писателя, порядковый номер)
+                        while queue.push((i, k)).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                });
+            }
+
+            let readers: Vec<_> = (0..num_threads)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut reads = Vec::with_capacity(items_per_thread);
+                        while reads.len() < items_per_thread {
+                            match queue.pop() {
+                                Some(item) => reads.push(item),
+                                None => thread::yield_now(),
+                            }
+                        }
+                        reads
+                    })
+                })
+                .collect();
+
+            let mut counts = HashMap::new();
+            for reader in readers {
+                for item in reader.join().unwrap() {
+                    *counts.entry(item).or_insert(0) += 1;
+                }
+            }
+            counts
+        });
+
+        // Ни один элемент не потерян и не прочитан дважды
+        assert_eq!(counts.len(), num_threads * items_per_thread);
+        assert!(counts.values().all(|&count| count == 1));
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,15 @@
+// Выравнивание по строке кэша: счётчики, которые меняют разные потоки,
+// не должны делить одну строку, иначе запись одного потока сбрасывает кэш другого
+
+use std::ops::Deref;
+
+#[repr(align(64))]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
@@ -0,0 +1,80 @@
+// Доступ к файлу буфера через отображение в память (libc::mmap с MAP_SHARED):
+// изменения видны другим процессам сразу, а на диск их сбрасывает msync.
+
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::ptr;
+
+use super::Storage;
+
+pub(super) struct MmapStorage {
+    ptr: *mut u8,
+    len: usize,
+    // Файл держим открытым, пока жива проекция
+    _file: File,
+}
+
+// Проекцией владеет только эта структура, а доступ к ней идёт через &self и &mut self,
+// как к обычному буферу в памяти
+unsafe impl Send for MmapStorage {}
+
+impl MmapStorage {
+    pub(super) fn new(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            ptr: ptr.cast(),
+            len,
+            _file: file,
+        })
+    }
+
+    fn check(&self, offset: u64, len: usize) -> io::Result<usize> {
+        let offset = offset as usize;
+        if offset + len > self.len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(offset)
+    }
+}
+
+impl Storage for MmapStorage {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let offset = self.check(offset, buf.len())?;
+        unsafe { ptr::copy_nonoverlapping(self.ptr.add(offset), buf.as_mut_ptr(), buf.len()) };
+        Ok(())
+    }
+
+    fn write_at(&mut self, data: &[u8], offset: u64) -> io::Result<()> {
+        let offset = self.check(offset, data.len())?;
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), self.ptr.add(offset), data.len()) };
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        if unsafe { libc::msync(self.ptr.cast(), self.len, libc::MS_SYNC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for MmapStorage {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr.cast(), self.len) };
+    }
+}
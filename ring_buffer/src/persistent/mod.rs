@@ -0,0 +1,538 @@
+// Кольцевой буфер записей, который хранится в файле и переживает перезапуск процесса.
+//
+// Раскладка файла:
+//   [заголовок A: 64 байта][заголовок B: 64 байта][данные: capacity байт]
+// Заголовок хранит ёмкость, позиции чтения и записи и номер поколения. Позиции растут
+// неограниченно, а место в данных вычисляется как позиция % capacity.
+// Заголовки пишутся по очереди: поколение n - в слот n % 2. Если запись заголовка
+// оборвалась, при открытии берётся целый заголовок с наибольшим поколением.
+// Новый файл сначала растягивается нулями и только потом получает первый заголовок;
+// если создание оборвалось между ними, файл целиком из нулей и создаётся заново.
+//
+// Каждая запись в данных: [длина: u32][crc32 содержимого: u32][содержимое].
+// Запись сначала попадает в данные и только потом в заголовок, но порядок сброса
+// страниц на диск не гарантирован. Поэтому при открытии записи проверяются заново,
+// и всё, начиная с первой битой, отбрасывается.
+//
+// С фичей mmap файл отображается в память через libc::mmap вместо pread/pwrite.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"RBPF";
+const HEADER_SIZE: u64 = 64;
+const DATA_OFFSET: u64 = 2 * HEADER_SIZE;
+const RECORD_HEADER_SIZE: u64 = 8;
+
+#[derive(Debug)]
+pub enum StoreError {
+    // Запись вместе с заголовком не помещается в свободное место
+    NoSpaceLeft,
+    // Ни один из заголовков файла не прошёл проверку или запись испорчена во время работы
+    Corrupt,
+    Io(io::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::NoSpaceLeft => write!(f, "Запись не помещается в буфер"),
+            StoreError::Corrupt => write!(f, "Файл буфера повреждён"),
+            StoreError::Io(error) => write!(f, "Ошибка ввода-вывода: {}", error),
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StoreError {
+    fn from(error: io::Error) -> Self {
+        StoreError::Io(error)
+    }
+}
+
+trait Storage {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()>;
+    fn write_at(&mut self, data: &[u8], offset: u64) -> io::Result<()>;
+    fn sync(&mut self) -> io::Result<()>;
+}
+
+struct FileStorage(File);
+
+impl Storage for FileStorage {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.0.read_exact_at(buf, offset)
+    }
+
+    fn write_at(&mut self, data: &[u8], offset: u64) -> io::Result<()> {
+        self.0.write_all_at(data, offset)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.0.sync_data()
+    }
+}
+
+#[cfg(feature = "mmap")]
+mod mmap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Header {
+    capacity: u64,
+    read: u64,
+    write: u64,
+    generation: u64,
+}
+
+impl Header {
+    fn encode(&self) -> [u8; HEADER_SIZE as usize] {
+        let mut bytes = [0; HEADER_SIZE as usize];
+        bytes[..4].copy_from_slice(MAGIC);
+        bytes[4..12].copy_from_slice(&self.capacity.to_le_bytes());
+        bytes[12..20].copy_from_slice(&self.read.to_le_bytes());
+        bytes[20..28].copy_from_slice(&self.write.to_le_bytes());
+        bytes[28..36].copy_from_slice(&self.generation.to_le_bytes());
+        let crc = crc32(&bytes[..36]);
+        bytes[36..40].copy_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8; HEADER_SIZE as usize]) -> Option<Self> {
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let crc = u32::from_le_bytes(bytes[36..40].try_into().unwrap());
+        if &bytes[..4] != MAGIC || crc != crc32(&bytes[..36]) {
+            return None;
+        }
+
+        let header = Header {
+            capacity: u64_at(4),
+            read: u64_at(12),
+            write: u64_at(20),
+            generation: u64_at(28),
+        };
+        let used = header.write.checked_sub(header.read)?;
+        (used <= header.capacity).then_some(header)
+    }
+}
+
+pub struct PersistentRingBuffer {
+    storage: Box<dyn Storage + Send>,
+    header: Header,
+    discarded: u64,
+}
+
+impl PersistentRingBuffer {
+    // Открывает буфер в файле path или создаёт новый на capacity байт данных.
+    // У существующего файла ёмкость берётся из заголовка, а capacity не используется.
+    pub fn open<P: AsRef<Path>>(path: P, capacity: usize) -> Result<Self, StoreError> {
+        let (file, created) = open_file(path.as_ref(), capacity)?;
+        Self::with_storage(Box::new(FileStorage(file)), capacity, created)
+    }
+
+    // То же, что open, но данные читаются и пишутся через отображение файла в память
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap<P: AsRef<Path>>(path: P, capacity: usize) -> Result<Self, StoreError> {
+        let (file, created) = open_file(path.as_ref(), capacity)?;
+        let storage = mmap::MmapStorage::new(file)?;
+        Self::with_storage(Box::new(storage), capacity, created)
+    }
+
+    fn with_storage(
+        storage: Box<dyn Storage + Send>,
+        capacity: usize,
+        created: bool,
+    ) -> Result<Self, StoreError> {
+        let mut rb = Self {
+            storage,
+            header: Header {
+                capacity: capacity as u64,
+                read: 0,
+                write: 0,
+                generation: 0,
+            },
+            discarded: 0,
+        };
+
+        if created {
+            rb.commit()?;
+        } else {
+            rb.header = rb.load_header()?;
+            rb.recover()?;
+        }
+        Ok(rb)
+    }
+
+    // Записывает запись целиком или не записывает вовсе
+    pub fn push(&mut self, record: &[u8]) -> Result<(), StoreError> {
+        let size = RECORD_HEADER_SIZE + record.len() as u64;
+        if record.len() > u32::MAX as usize || size > self.available() as u64 {
+            return Err(StoreError::NoSpaceLeft);
+        }
+
+        let mut prefix = [0; RECORD_HEADER_SIZE as usize];
+        prefix[..4].copy_from_slice(&(record.len() as u32).to_le_bytes());
+        prefix[4..].copy_from_slice(&crc32(record).to_le_bytes());
+
+        let write = self.header.write;
+        self.write_data(write, &prefix)?;
+        self.write_data(write + RECORD_HEADER_SIZE, record)?;
+
+        self.header.write += size;
+        self.commit()
+    }
+
+    // Извлекает самую старую запись; None - если буфер пуст
+    pub fn pop(&mut self) -> Result<Option<Vec<u8>>, StoreError> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        let record = self
+            .read_record(self.header.read)?
+            .ok_or(StoreError::Corrupt)?;
+        self.header.read += RECORD_HEADER_SIZE + record.len() as u64;
+        self.commit()?;
+        Ok(Some(record))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Сколько байт занимают непрочитанные записи вместе с их заголовками
+    pub fn len(&self) -> usize {
+        (self.header.write - self.header.read) as usize
+    }
+
+    pub fn available(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.header.capacity as usize
+    }
+
+    // Номер поколения заголовка: увеличивается при каждом изменении
+    pub fn generation(&self) -> u64 {
+        self.header.generation
+    }
+
+    // Сколько байт битых записей было отброшено при открытии
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+
+    // Сбрасывает данные и заголовок на диск
+    pub fn sync(&mut self) -> Result<(), StoreError> {
+        Ok(self.storage.sync()?)
+    }
+
+    fn commit(&mut self) -> Result<(), StoreError> {
+        self.header.generation += 1;
+        let slot = self.header.generation % 2;
+        self.storage
+            .write_at(&self.header.encode(), slot * HEADER_SIZE)?;
+        Ok(())
+    }
+
+    fn load_header(&self) -> Result<Header, StoreError> {
+        let mut best: Option<Header> = None;
+        for slot in 0..2 {
+            let mut bytes = [0; HEADER_SIZE as usize];
+            self.storage.read_at(&mut bytes, slot * HEADER_SIZE)?;
+            if let Some(header) = Header::decode(&bytes)
+                && best.is_none_or(|best| header.generation > best.generation)
+            {
+                best = Some(header);
+            }
+        }
+        best.ok_or(StoreError::Corrupt)
+    }
+
+    // Проверяет записи от позиции чтения до позиции записи и обрезает всё с первой битой
+    fn recover(&mut self) -> Result<(), StoreError> {
+        let mut pos = self.header.read;
+        while pos < self.header.write {
+            match self.read_record(pos)? {
+                Some(record) => pos += RECORD_HEADER_SIZE + record.len() as u64,
+                None => break,
+            }
+        }
+
+        if pos != self.header.write {
+            self.discarded = self.header.write - pos;
+            self.header.write = pos;
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    // Запись на позиции pos или None, если она не умещается до позиции записи
+    // или не сходится контрольная сумма
+    fn read_record(&self, pos: u64) -> Result<Option<Vec<u8>>, StoreError> {
+        let remaining = self.header.write - pos;
+        if remaining < RECORD_HEADER_SIZE {
+            return Ok(None);
+        }
+
+        let mut prefix = [0; RECORD_HEADER_SIZE as usize];
+        self.read_data(pos, &mut prefix)?;
+        let len = u32::from_le_bytes(prefix[..4].try_into().unwrap()) as u64;
+        let crc = u32::from_le_bytes(prefix[4..].try_into().unwrap());
+        if len > remaining - RECORD_HEADER_SIZE {
+            return Ok(None);
+        }
+
+        let mut record = vec![0; len as usize];
+        self.read_data(pos + RECORD_HEADER_SIZE, &mut record)?;
+        Ok((crc32(&record) == crc).then_some(record))
+    }
+
+    // Данные идут по кругу, поэтому чтение и запись делятся максимум на два куска
+    fn read_data(&self, pos: u64, buf: &mut [u8]) -> io::Result<()> {
+        let (first, offset) = self.split(pos, buf.len());
+        self.storage.read_at(&mut buf[..first], offset)?;
+        self.storage.read_at(&mut buf[first..], DATA_OFFSET)
+    }
+
+    fn write_data(&mut self, pos: u64, data: &[u8]) -> io::Result<()> {
+        let (first, offset) = self.split(pos, data.len());
+        self.storage.write_at(&data[..first], offset)?;
+        self.storage.write_at(&data[first..], DATA_OFFSET)
+    }
+
+    // Длина куска до конца области данных и его смещение в файле
+    fn split(&self, pos: u64, len: usize) -> (usize, u64) {
+        let slot = pos % self.header.capacity;
+        let first = std::cmp::min(len as u64, self.header.capacity - slot);
+        (first as usize, DATA_OFFSET + slot)
+    }
+}
+
+fn open_file(path: &Path, capacity: usize) -> io::Result<(File, bool)> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+
+    let len = file.metadata()?.len();
+    let created = len == 0 || is_zeroed(&file, len)?;
+    if created {
+        file.set_len(DATA_OFFSET + capacity as u64)?;
+    }
+    Ok((file, created))
+}
+
+// Рабочий файл не бывает нулевым: хотя бы один заголовок уже записан
+fn is_zeroed(file: &File, len: u64) -> io::Result<bool> {
+    let mut chunk = [0; 4096];
+    let mut offset = 0;
+    while offset < len {
+        let size = std::cmp::min(len - offset, chunk.len() as u64) as usize;
+        file.read_exact_at(&mut chunk[..size], offset)?;
+        if chunk[..size].iter().any(|&byte| byte != 0) {
+            return Ok(false);
+        }
+        offset += size as u64;
+    }
+    Ok(true)
+}
+
+// CRC-32 (IEEE), побитовый вариант без таблицы
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("ring-buffer-{}-{}.bin", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_survives_reopen() {
+        let path = temp_path("reopen");
+        {
+            let mut rb = PersistentRingBuffer::open(&path, 64).unwrap();
+            rb.push(b"first").unwrap();
+            rb.push(b"second").unwrap();
+            rb.push(b"third").unwrap();
+            assert_eq!(rb.pop().unwrap(), Some(b"first".to_vec()));
+        }
+
+        let mut rb = PersistentRingBuffer::open(&path, 1000).unwrap();
+        assert_eq!(rb.capacity(), 64);
+        assert_eq!(rb.discarded(), 0);
+        assert_eq!(rb.pop().unwrap(), Some(b"second".to_vec()));
+        assert_eq!(rb.pop().unwrap(), Some(b"third".to_vec()));
+        assert_eq!(rb.pop().unwrap(), None);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_wraps_and_rejects() {
+        let path = temp_path("wrap");
+        let mut rb = PersistentRingBuffer::open(&path, 32).unwrap();
+        for i in 0..20u8 {
+            // Каждая запись - 8 байт заголовка и 10 байт данных, так что они
+            // регулярно переходят через край области данных
+            rb.push(&[i; 10]).unwrap();
+            assert!(matches!(rb.push(&[0; 10]), Err(StoreError::NoSpaceLeft)));
+            assert_eq!(rb.pop().unwrap(), Some(vec![i; 10]));
+        }
+        assert!(matches!(rb.push(&[0; 25]), Err(StoreError::NoSpaceLeft)));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_torn_record_is_discarded() {
+        let path = temp_path("torn");
+        {
+            let mut rb = PersistentRingBuffer::open(&path, 64).unwrap();
+            rb.push(b"kept").unwrap();
+            rb.push(b"torn").unwrap();
+        }
+
+        // Заголовок уже обновлён, но содержимое второй записи не дошло до диска
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.write_all_at(b"XX", DATA_OFFSET + 12 + RECORD_HEADER_SIZE)
+            .unwrap();
+        drop(file);
+
+        let mut rb = PersistentRingBuffer::open(&path, 64).unwrap();
+        assert_eq!(rb.discarded(), 12);
+        assert_eq!(rb.pop().unwrap(), Some(b"kept".to_vec()));
+        assert_eq!(rb.pop().unwrap(), None);
+        // Место битой записи снова свободно
+        rb.push(b"new").unwrap();
+        assert_eq!(rb.pop().unwrap(), Some(b"new".to_vec()));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_torn_header_falls_back() {
+        let path = temp_path("header");
+        let generation = {
+            let mut rb = PersistentRingBuffer::open(&path, 64).unwrap();
+            rb.push(b"a").unwrap();
+            rb.push(b"b").unwrap();
+            rb.generation()
+        };
+
+        // Портим последний заголовок: остаётся предыдущий, в котором "b" ещё нет
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.write_all_at(&[0xff; 8], (generation % 2) * HEADER_SIZE + 20)
+            .unwrap();
+        drop(file);
+
+        let mut rb = PersistentRingBuffer::open(&path, 64).unwrap();
+        assert_eq!(rb.generation(), generation - 1);
+        assert_eq!(rb.pop().unwrap(), Some(b"a".to_vec()));
+        assert_eq!(rb.pop().unwrap(), None);
+
+        // Оба заголовка испорчены - открыть файл нельзя
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.write_all_at(&[0; 2 * HEADER_SIZE as usize], 0)
+            .unwrap();
+        drop(file);
+        assert!(matches!(
+            PersistentRingBuffer::open(&path, 64),
+            Err(StoreError::Corrupt)
+        ));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_interrupted_create() {
+        // Файл уже растянут, но первый заголовок записать не успели
+        let path = temp_path("create");
+        File::create(&path)
+            .unwrap()
+            .set_len(DATA_OFFSET + 32)
+            .unwrap();
+
+        {
+            let mut rb = PersistentRingBuffer::open(&path, 64).unwrap();
+            assert_eq!(rb.capacity(), 64);
+            rb.push(b"fresh").unwrap();
+        }
+        let mut rb = PersistentRingBuffer::open(&path, 16).unwrap();
+        assert_eq!(rb.capacity(), 64);
+        assert_eq!(rb.pop().unwrap(), Some(b"fresh".to_vec()));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_error_display() {
+        assert_eq!(StoreError::Corrupt.to_string(), "Файл буфера повреждён");
+        assert!(StoreError::NoSpaceLeft.source().is_none());
+
+        let error = StoreError::from(io::Error::other("диск"));
+        assert_eq!(error.to_string(), "Ошибка ввода-вывода: диск");
+        assert_eq!(error.source().unwrap().to_string(), "диск");
+
+        // Ошибку можно вернуть через ? из функции с Box<dyn Error>
+        let boxed: Box<dyn Error> = StoreError::Corrupt.into();
+        assert!(boxed.is::<StoreError>());
+    }
+
+    #[test]
+    fn test_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<PersistentRingBuffer>();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mmap_survives_reopen() {
+        let path = temp_path("mmap");
+        {
+            let mut rb = PersistentRingBuffer::open_mmap(&path, 32).unwrap();
+            for i in 0..5u8 {
+                rb.push(&[i; 9]).unwrap();
+                rb.pop().unwrap();
+            }
+            rb.push(b"mapped").unwrap();
+            rb.sync().unwrap();
+        }
+
+        // Формат общий: файл, записанный через mmap, читается и через pread
+        let mut rb = PersistentRingBuffer::open(&path, 32).unwrap();
+        assert_eq!(rb.pop().unwrap(), Some(b"mapped".to_vec()));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,349 @@
+// Кольцевой буфер без блокировок для одного писателя и одного читателя (SPSC).
+//
+// Писатель двигает только tail, читатель - только head, поэтому каждая операция
+// укладывается в пару атомарных загрузок и одну запись. Индексы растут неограниченно
+// (с переполнением по модулю usize), так что полный и пустой буфер различаются без
+// отдельного флага. Массив ячеек выделяется с длиной, округлённой вверх до степени
+// двойки, и ячейка вычисляется как индекс & mask: степень двойки делит 2^usize::BITS,
+// поэтому переполнение индекса не сдвигает ячейки. Ёмкость при этом остаётся ровно
+// capacity - лишние ячейки просто не используются.
+// Каждая половина кэширует последнее увиденное значение чужого индекса и
+// перечитывает его только тогда, когда закэшированного значения не хватает.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::padded::CachePadded;
+
+struct Shared<T> {
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+    capacity: usize,
+    // Длина data минус один
+    mask: usize,
+    data: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+// Ячейка между head и tail принадлежит читателю, остальные - писателю,
+// поэтому к одной ячейке никогда не обращаются два потока одновременно
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn slot(&self, idx: usize) -> *mut MaybeUninit<T> {
+        self.data[idx & self.mask].get()
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let head = *self.head.0.get_mut();
+        let tail = *self.tail.0.get_mut();
+        for idx in 0..tail.wrapping_sub(head) {
+            unsafe { (*self.slot(head.wrapping_add(idx))).assume_init_drop() };
+        }
+    }
+}
+
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+    tail: usize,
+    cached_head: usize,
+}
+
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+    head: usize,
+    cached_tail: usize,
+}
+
+// Создаёт буфер на capacity элементов и делит его на писателя и читателя
+pub fn ring<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    ring_from(capacity, 0)
+}
+
+// Индексы начинаются со start; тесты так проверяют переполнение индексов
+fn ring_from<T>(capacity: usize, start: usize) -> (Producer<T>, Consumer<T>) {
+    let slots = capacity.next_power_of_two();
+    let shared = Arc::new(Shared {
+        head: CachePadded(AtomicUsize::new(start)),
+        tail: CachePadded(AtomicUsize::new(start)),
+        capacity,
+        mask: slots - 1,
+        data: (0..slots)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+    });
+
+    (
+        Producer {
+            shared: shared.clone(),
+            tail: start,
+            cached_head: start,
+        },
+        Consumer {
+            shared,
+            head: start,
+            cached_tail: start,
+        },
+    )
+}
+
+impl<T> Producer<T> {
+    // Добавляет элемент в конец очереди; если места нет, возвращает его обратно
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.reserve(1) == 0 {
+            return Err(item);
+        }
+
+        unsafe { (*self.shared.slot(self.tail)).write(item) };
+        self.tail = self.tail.wrapping_add(1);
+        self.shared.tail.store(self.tail, Ordering::Release);
+
+        Ok(())
+    }
+
+    // Количество элементов в буфере с точки зрения писателя: читатель мог
+    // уже забрать часть из них
+    pub fn len(&self) -> usize {
+        self.tail
+            .wrapping_sub(self.shared.head.load(Ordering::Acquire))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Сколько элементов точно можно записать
+    pub fn available(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    // Сколько ячеек свободно; head перечитывается, только если по кэшу их меньше wanted
+    fn reserve(&mut self, wanted: usize) -> usize {
+        let free = self.capacity() - self.tail.wrapping_sub(self.cached_head);
+        if free >= wanted {
+            return free;
+        }
+
+        self.cached_head = self.shared.head.load(Ordering::Acquire);
+        self.capacity() - self.tail.wrapping_sub(self.cached_head)
+    }
+}
+
+impl<T: Copy> Producer<T> {
+    // Записывает столько элементов из buf, сколько помещается, и возвращает их количество
+    pub fn write(&mut self, buf: &[T]) -> usize {
+        let count = std::cmp::min(buf.len(), self.reserve(buf.len()));
+        for (idx, &item) in buf[..count].iter().enumerate() {
+            unsafe { (*self.shared.slot(self.tail.wrapping_add(idx))).write(item) };
+        }
+
+        self.tail = self.tail.wrapping_add(count);
+        self.shared.tail.store(self.tail, Ordering::Release);
+
+        count
+    }
+}
+
+impl<T> Consumer<T> {
+    pub fn pop(&mut self) -> Option<T> {
+        if self.ready(1) == 0 {
+            return None;
+        }
+
+        let item = unsafe { (*self.shared.slot(self.head)).assume_init_read() };
+        self.head = self.head.wrapping_add(1);
+        self.shared.head.store(self.head, Ordering::Release);
+
+        Some(item)
+    }
+
+    // Количество элементов, которые точно можно прочитать
+    pub fn len(&self) -> usize {
+        self.shared
+            .tail
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    // Сколько элементов готово к чтению; tail перечитывается, только если по кэшу их меньше wanted
+    fn ready(&mut self, wanted: usize) -> usize {
+        let ready = self.cached_tail.wrapping_sub(self.head);
+        if ready >= wanted {
+            return ready;
+        }
+
+        self.cached_tail = self.shared.tail.load(Ordering::Acquire);
+        self.cached_tail.wrapping_sub(self.head)
+    }
+}
+
+impl<T: Copy> Consumer<T> {
+    // Читает не больше n элементов: если в буфере меньше, возвращается только то, что есть
+    pub fn read(&mut self, n: usize) -> Vec<T> {
+        let count = std::cmp::min(n, self.ready(n));
+        let result = (0..count)
+            .map(|idx| unsafe { (*self.shared.slot(self.head.wrapping_add(idx))).assume_init() })
+            .collect();
+
+        self.head = self.head.wrapping_add(count);
+        self.shared.head.store(self.head, Ordering::Release);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::VecDeque;
+    use std::thread;
+
+    #[test]
+    fn test_push_pop() {
+        let (mut producer, mut consumer) = ring(2);
+        assert_eq!(producer.push(String::from("a")), Ok(()));
+        assert_eq!(producer.push(String::from("b")), Ok(()));
+        assert_eq!(producer.push(String::from("c")), Err(String::from("c")));
+        assert_eq!(consumer.pop(), Some(String::from("a")));
+        assert_eq!(producer.push(String::from("c")), Ok(()));
+        assert_eq!(consumer.pop(), Some(String::from("b")));
+        assert_eq!(consumer.pop(), Some(String::from("c")));
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn test_write_read_wraps() {
+        let (mut producer, mut consumer) = ring(4);
+        assert_eq!(producer.write(b"abc"), 3);
+        assert_eq!(consumer.read(2), b"ab");
+        assert_eq!(producer.write(b"defg"), 3);
+        assert_eq!(producer.available(), 0);
+        assert_eq!(consumer.len(), 4);
+        assert_eq!(consumer.read(10), b"cdef");
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_indices_overflow() {
+        // Ёмкость 3 не делит 2^usize::BITS: с индексом % capacity после переполнения
+        // чтение пошло бы не из той ячейки
+        let (mut producer, mut consumer) = ring_from(3, usize::MAX - 4);
+        for round in 0..4u8 {
+            let buf = [round, round + 10, round + 20];
+            assert_eq!(producer.write(&buf), 3);
+            assert_eq!(producer.push(0), Err(0));
+            assert_eq!(consumer.len(), 3);
+            assert_eq!(consumer.read(2), buf[..2]);
+            assert_eq!(consumer.pop(), Some(buf[2]));
+            assert!(consumer.is_empty());
+        }
+        assert!(producer.tail < usize::MAX - 4);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let (mut producer, mut consumer) = ring(0);
+        assert_eq!(producer.push(1), Err(1));
+        assert_eq!(producer.write(&[1, 2]), 0);
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn test_drops_remaining_items() {
+        let item = Arc::new(());
+        let (mut producer, mut consumer) = ring(3);
+        for _ in 0..3 {
+            producer.push(item.clone()).unwrap();
+        }
+        consumer.pop();
+        assert_eq!(Arc::strong_count(&item), 3);
+
+        drop(producer);
+        drop(consumer);
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn test_threads_keep_order() {
+        let total = 100_000u64;
+        let (mut producer, mut consumer) = ring(64);
+
+        let writer = thread::spawn(move || {
+            for i in 0..total {
+                while producer.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < total {
+            match consumer.pop() {
+                Some(i) => {
+                    assert_eq!(i, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        writer.join().unwrap();
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Write(Vec<u8>),
+        Read(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            prop::collection::vec(any::<u8>(), 0..8).prop_map(Op::Write),
+            (0..8usize).prop_map(Op::Read),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_vec_deque(
+            size in 1..8usize,
+            start in prop_oneof![Just(0), usize::MAX - 64..=usize::MAX],
+            ops in prop::collection::vec(op(), 0..64),
+        ) {
+            let (mut producer, mut consumer) = ring_from(size, start);
+            let mut model = VecDeque::new();
+
+            for op in ops {
+                match op {
+                    Op::Write(buf) => {
+                        let expected = buf.len().min(size - model.len());
+                        model.extend(&buf[..expected]);
+                        prop_assert_eq!(producer.write(&buf), expected);
+                    }
+                    Op::Read(n) => {
+                        let expected: Vec<u8> = model.drain(..n.min(model.len())).collect();
+                        prop_assert_eq!(consumer.read(n), expected);
+                    }
+                }
+                prop_assert_eq!(producer.len(), model.len());
+                prop_assert_eq!(consumer.len(), model.len());
+                prop_assert_eq!(producer.available(), size - model.len());
+            }
+        }
+    }
+}
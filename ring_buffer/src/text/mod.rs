@@ -0,0 +1,320 @@
+// Текстовый режим: в буфер записываются только целые символы UTF-8,
+// поэтому при чтении строка никогда не разрезает символ пополам.
+
+use crate::{Metrics, OverflowPolicy, RingBuffer};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Written {
+    pub chars: usize,
+    pub bytes: usize,
+}
+
+#[derive(Debug)]
+pub struct TextRingBuffer {
+    inner: RingBuffer<u8>,
+}
+
+impl TextRingBuffer {
+    // size - размер буфера в байтах
+    pub fn new(size: usize) -> Self {
+        Self::with_policy(size, OverflowPolicy::default())
+    }
+
+    pub fn with_policy(size: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            inner: RingBuffer::with_policy(size, policy),
+        }
+    }
+
+    // Записывает самый длинный префикс из целых символов, который помещается в буфер
+    // (в режиме Grow буфер сначала растёт).
+    // В режиме перезаписи строка принимается целиком: вытесняются самые старые символы,
+    // а если строка длиннее буфера, в нём остаются только последние символы.
+    pub fn write(&mut self, s: &str) -> Written {
+        if self.inner.policy() == OverflowPolicy::OverwriteOldest {
+            self.make_room(s);
+            return Written {
+                chars: s.chars().count(),
+                bytes: s.len(),
+            };
+        }
+
+        self.inner.reserve(s.len());
+        let mut end = std::cmp::min(s.len(), self.inner.available());
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let bytes = self.inner.write(&s.as_bytes()[..end]);
+        if bytes < s.len() {
+            self.inner.metrics.rejected_writes += 1;
+        }
+        Written {
+            chars: s[..bytes].chars().count(),
+            bytes,
+        }
+    }
+
+    // Читает не больше max_bytes байт, не разрезая символы. Если первый символ
+    // длиннее max_bytes, он всё равно читается целиком: иначе цикл чтения по
+    // одному байту никогда бы не продвинулся.
+    pub fn read(&mut self, max_bytes: usize) -> String {
+        self.inner.record_empty_read(max_bytes);
+
+        let mut end = std::cmp::min(max_bytes, self.inner.len());
+        while self.inner.get(end).is_some_and(|&b| is_continuation(b)) {
+            end -= 1;
+        }
+        if end == 0 && max_bytes > 0 {
+            end = self.peek_char().map_or(0, char::len_utf8);
+        }
+
+        self.take(end)
+    }
+
+    // Первый символ буфера; буфер не меняется
+    pub fn peek_char(&self) -> Option<char> {
+        let mut bytes = [0; 4];
+        let mut len = 0;
+        while let Some(&b) = self.inner.get(len).filter(|_| len < bytes.len()) {
+            if len > 0 && !is_continuation(b) {
+                break;
+            }
+            bytes[len] = b;
+            len += 1;
+        }
+        core::str::from_utf8(&bytes[..len]).ok()?.chars().next()
+    }
+
+    // Читает не больше n символов
+    pub fn read_chars(&mut self, n: usize) -> String {
+        self.inner.record_empty_read(n);
+
+        let mut end = 0;
+        let mut chars = 0;
+        while let Some(&b) = self.inner.get(end) {
+            if !is_continuation(b) {
+                if chars == n {
+                    break;
+                }
+                chars += 1;
+            }
+            end += 1;
+        }
+
+        self.take(end)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.inner.is_full()
+    }
+
+    pub fn available(&self) -> usize {
+        self.inner.available()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    pub fn read_idx(&self) -> usize {
+        self.inner.read_idx()
+    }
+
+    pub fn write_idx(&self) -> usize {
+        self.inner.write_idx()
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.inner.policy()
+    }
+
+    // Сколько байт было потеряно из-за перезаписи
+    pub fn dropped(&self) -> u64 {
+        self.inner.dropped()
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        self.inner.metrics()
+    }
+
+    // Освобождает место под хвост строки s, вытесняя целые символы, и записывает его
+    fn make_room(&mut self, s: &str) {
+        let mut start = s.len().saturating_sub(self.inner.capacity());
+        while !s.is_char_boundary(start) {
+            start += 1;
+        }
+        let tail = &s[start..];
+
+        let mut evict = tail.len().saturating_sub(self.inner.available());
+        while self.inner.get(evict).is_some_and(|&b| is_continuation(b)) {
+            evict += 1;
+        }
+
+        self.inner.discard(evict);
+        self.inner.dropped += start as u64;
+        self.inner.write(tail.as_bytes());
+    }
+
+    fn take(&mut self, bytes: usize) -> String {
+        // В буфер попадают только целые символы, поэтому срез всегда корректен
+        String::from_utf8(self.inner.read(bytes)).expect("TextRingBuffer содержит только UTF-8")
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii() {
+        let mut rb = TextRingBuffer::new(3);
+        assert_eq!(rb.write("abcd"), Written { chars: 3, bytes: 3 });
+        assert_eq!(rb.read(2), "ab");
+        assert_eq!(rb.read(5), "c");
+        assert!(rb.is_empty());
+    }
+
+    #[test]
+    fn test_cyrillic_write_keeps_whole_chars() {
+        // Каждая кириллическая буква занимает 2 байта
+        let mut rb = TextRingBuffer::new(5);
+        assert_eq!(rb.write("привет"), Written { chars: 2, bytes: 4 });
+        assert_eq!(rb.available(), 1);
+        assert_eq!(rb.write("ы"), Written { chars: 0, bytes: 0 });
+        assert_eq!(rb.write("!"), Written { chars: 1, bytes: 1 });
+        assert_eq!(rb.read(10), "пр!");
+    }
+
+    #[test]
+    fn test_cyrillic_read_never_splits() {
+        let mut rb = TextRingBuffer::new(8);
+        rb.write("мир");
+        assert_eq!(rb.peek_char(), Some('м'));
+        assert_eq!(rb.read(3), "м");
+        assert_eq!(rb.read(2), "и");
+        assert_eq!(rb.read(2), "р");
+        assert_eq!(rb.peek_char(), None);
+    }
+
+    #[test]
+    fn test_read_takes_whole_first_char() {
+        // Байтовый цикл чтения не должен зависать на многобайтовом символе
+        let mut rb = TextRingBuffer::new(8);
+        rb.write("я🦀a");
+        let mut chunks = Vec::new();
+        while !rb.is_empty() {
+            chunks.push(rb.read(1));
+        }
+        assert_eq!(chunks, ["я", "🦀", "a"]);
+        assert_eq!(rb.read(0), "");
+    }
+
+    #[test]
+    fn test_emoji() {
+        // Эмодзи занимает 4 байта
+        let mut rb = TextRingBuffer::new(6);
+        assert_eq!(rb.write("a🦀🦀"), Written { chars: 2, bytes: 5 });
+        assert_eq!(rb.read(4), "a");
+        assert_eq!(rb.write("🦀"), Written { chars: 0, bytes: 0 });
+        assert_eq!(rb.read(3), "🦀");
+        assert_eq!(rb.write("🦀"), Written { chars: 1, bytes: 4 });
+        assert_eq!(rb.read(8), "🦀");
+    }
+
+    #[test]
+    fn test_read_chars_across_wrap() {
+        let mut rb = TextRingBuffer::new(7);
+        rb.write("аб");
+        rb.read(4);
+        assert_eq!(rb.write("в🦀г"), Written { chars: 2, bytes: 6 });
+        assert_eq!(rb.read_chars(1), "в");
+        assert_eq!(rb.read_chars(5), "🦀");
+        assert_eq!(rb.read_chars(1), "");
+    }
+
+    #[test]
+    fn test_overwrite_evicts_whole_chars() {
+        let mut rb = TextRingBuffer::with_policy(6, OverflowPolicy::OverwriteOldest);
+        rb.write("абв");
+        // Под "г" нужно 2 байта: вытесняется "а"
+        assert_eq!(rb.write("г"), Written { chars: 1, bytes: 2 });
+        assert_eq!(rb.dropped(), 2);
+        // Под "🦀" нужно 4 байта: вытесняются "б" и "в"
+        rb.write("🦀");
+        assert_eq!(rb.dropped(), 6);
+        assert_eq!(rb.read(10), "г🦀");
+    }
+
+    #[test]
+    fn test_overwrite_keeps_tail_of_long_string() {
+        let mut rb = TextRingBuffer::with_policy(5, OverflowPolicy::OverwriteOldest);
+        assert_eq!(
+            rb.write("привет"),
+            Written {
+                chars: 6,
+                bytes: 12
+            }
+        );
+        // Влезают только 2 последних символа, "е" вытесняется целиком
+        assert_eq!(rb.read(10), "ет");
+        assert_eq!(rb.dropped(), 8);
+
+        rb.write("a🦀");
+        rb.write("b");
+        assert_eq!(rb.read(10), "🦀b");
+        assert_eq!(rb.dropped(), 9);
+    }
+
+    #[test]
+    fn test_grow_keeps_whole_chars() {
+        let mut rb = TextRingBuffer::with_policy(2, OverflowPolicy::Grow { max: 7 });
+        assert_eq!(rb.write("ёж"), Written { chars: 2, bytes: 4 });
+        assert_eq!(rb.capacity(), 4);
+        // Больше 7 байт буфер не вырастет, а "🦀" в оставшиеся 3 байта не влезает
+        assert_eq!(rb.write("!🦀"), Written { chars: 1, bytes: 1 });
+        assert_eq!(rb.capacity(), 7);
+        assert_eq!(rb.read(10), "ёж!");
+    }
+
+    #[test]
+    fn test_empty_reads() {
+        let mut rb = TextRingBuffer::new(4);
+        // Чтение нуля байт или символов - не попытка прочитать из пустого буфера
+        assert_eq!(rb.read(0), "");
+        assert_eq!(rb.read_chars(0), "");
+        assert_eq!(rb.metrics().empty_reads, 0);
+
+        assert_eq!(rb.read(1), "");
+        assert_eq!(rb.read_chars(1), "");
+        assert_eq!(rb.metrics().empty_reads, 2);
+    }
+
+    #[test]
+    fn test_mixed_stream_roundtrip() {
+        let input = "Ёж 🦔 и ёлка 🎄!";
+        let mut rb = TextRingBuffer::new(5);
+        let mut rest = input;
+        let mut output = String::new();
+
+        while !rest.is_empty() || !rb.is_empty() {
+            let written = rb.write(rest);
+            rest = &rest[written.bytes..];
+            output.push_str(&rb.read(4));
+        }
+
+        assert_eq!(output, input);
+    }
+}